@@ -0,0 +1,48 @@
+/* Build the library with `cargo rustc --release --features capi --crate-type cdylib`. */
+
+#ifndef SMC_H
+#define SMC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SMC_OK 0
+#define SMC_ERR_INVALID_ARGUMENT 1
+#define SMC_ERR_BUFFER_TOO_SMALL 2
+#define SMC_ERR_FAILED_TO_OPEN 3
+#define SMC_ERR_KEY_NOT_FOUND 4
+#define SMC_ERR_NOT_READABLE 5
+#define SMC_ERR_NOT_WRITABLE 6
+#define SMC_ERR_NOT_PRIVILEGED 7
+#define SMC_ERR_WRITE_DENIED 8
+#define SMC_ERR_UNSUPPORTED 9
+#define SMC_ERR_PANIC 10
+#define SMC_ERR_OTHER 255
+
+typedef struct smc_t smc_t;
+
+int smc_open(smc_t **out);
+void smc_close(smc_t *smc);
+
+/* `len` holds the capacity of `buf` on input and the value size on output.
+ * `data_type` may be NULL. */
+int smc_read_key(const smc_t *smc, uint32_t key, uint32_t *data_type,
+                 uint8_t *buf, size_t *len);
+/* `len` must equal the key's size, otherwise SMC_ERR_INVALID_ARGUMENT. */
+int smc_write_key(const smc_t *smc, uint32_t key, const uint8_t *buf,
+                  size_t len);
+
+/* `len` holds the capacity of `keys` on input and the key count on output. */
+int smc_list_keys(const smc_t *smc, uint32_t *keys, size_t *len);
+
+const char *smc_strerror(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::{ConversionError, SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Above(FourCharCode, f64),
+    Below(FourCharCode, f64),
+    Equals(FourCharCode, f64),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    #[inline]
+    pub fn above(key: FourCharCode, value: f64) -> Condition {
+        Condition::Above(key, value)
+    }
+
+    #[inline]
+    pub fn below(key: FourCharCode, value: f64) -> Condition {
+        Condition::Below(key, value)
+    }
+
+    #[inline]
+    pub fn equals(key: FourCharCode, value: f64) -> Condition {
+        Condition::Equals(key, value)
+    }
+
+    pub fn and(self, other: Condition) -> Condition {
+        Condition::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Condition) -> Condition {
+        Condition::Or(Box::new(self), Box::new(other))
+    }
+
+    fn collect_keys(&self, keys: &mut Vec<FourCharCode>) {
+        match self {
+            Condition::Above(key, _) | Condition::Below(key, _) | Condition::Equals(key, _) => {
+                if !keys.contains(key) {
+                    keys.push(*key);
+                }
+            }
+            Condition::And(a, b) | Condition::Or(a, b) => {
+                a.collect_keys(keys);
+                b.collect_keys(keys);
+            }
+        }
+    }
+
+    fn eval(&self, values: &HashMap<FourCharCode, f64>) -> bool {
+        match self {
+            Condition::Above(key, limit) => values.get(key).map_or(false, |v| v > limit),
+            Condition::Below(key, limit) => values.get(key).map_or(false, |v| v < limit),
+            Condition::Equals(key, limit) => values
+                .get(key)
+                .map_or(false, |v| (v - limit).abs() < std::f64::EPSILON),
+            Condition::And(a, b) => a.eval(values) && b.eval(values),
+            Condition::Or(a, b) => a.eval(values) || b.eval(values),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub name: String,
+    pub condition: Condition,
+    pub duration: Duration,
+}
+
+impl Threshold {
+    pub fn new<S: Into<String>>(name: S, condition: Condition) -> Threshold {
+        Threshold {
+            name: name.into(),
+            condition,
+            duration: Duration::from_secs(0),
+        }
+    }
+
+    pub fn for_duration(mut self, duration: Duration) -> Threshold {
+        self.duration = duration;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AlertState {
+    Tripped,
+    Cleared,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub name: String,
+    pub state: AlertState,
+    pub timestamp: Instant,
+}
+
+type Callback = Box<dyn FnMut(&AlertEvent) + Send>;
+
+struct Alert {
+    threshold: Threshold,
+    callback: Option<Callback>,
+    since: Option<Instant>,
+    tripped: bool,
+}
+
+#[derive(Default)]
+pub struct Alerts {
+    alerts: Vec<Alert>,
+}
+
+impl Alerts {
+    pub fn new() -> Alerts {
+        Alerts::default()
+    }
+
+    pub fn add(&mut self, threshold: Threshold) {
+        self.push(threshold, None);
+    }
+
+    pub fn on<F>(&mut self, threshold: Threshold, callback: F)
+    where
+        F: FnMut(&AlertEvent) + Send + 'static,
+    {
+        self.push(threshold, Some(Box::new(callback)));
+    }
+
+    fn push(&mut self, threshold: Threshold, callback: Option<Callback>) {
+        self.alerts.push(Alert {
+            threshold,
+            callback,
+            since: None,
+            tripped: false,
+        });
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.alerts.len();
+        self.alerts.retain(|alert| alert.threshold.name != name);
+        self.alerts.len() != len
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.alerts.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    pub fn is_tripped(&self, name: &str) -> bool {
+        self.alerts
+            .iter()
+            .any(|alert| alert.tripped && alert.threshold.name == name)
+    }
+
+    pub fn check(&mut self, smc: &SMC) -> Result<Vec<AlertEvent>, SMCError> {
+        let mut keys = Vec::new();
+        for alert in self.alerts.iter() {
+            alert.threshold.condition.collect_keys(&mut keys);
+        }
+
+        let mut values = HashMap::with_capacity(keys.len());
+        for key in keys {
+            let reading = match smc.read_sensor(key) {
+                Ok(reading) => reading,
+                Err(ref err) if err.is_not_found() => continue,
+                Err(err) => return Err(err),
+            };
+            match reading.as_f64() {
+                Some(value) => {
+                    values.insert(key, value);
+                }
+                None => {
+                    return Err(SMCError::TypeMismatch(ConversionError {
+                        key: Some(key),
+                        type_name: "f64",
+                        data_type: reading.value.data_type(),
+                        data: reading.value.data().to_vec(),
+                    }))
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for alert in self.alerts.iter_mut() {
+            let state = if alert.threshold.condition.eval(&values) {
+                let since = *alert.since.get_or_insert(now);
+                if alert.tripped || now.duration_since(since) < alert.threshold.duration {
+                    continue;
+                }
+                alert.tripped = true;
+                AlertState::Tripped
+            } else {
+                alert.since = None;
+                if !alert.tripped {
+                    continue;
+                }
+                alert.tripped = false;
+                AlertState::Cleared
+            };
+
+            let event = AlertEvent {
+                name: alert.threshold.name.clone(),
+                state,
+                timestamp: now,
+            };
+            if let Some(ref mut callback) = alert.callback {
+                callback(&event);
+            }
+            events.push(event);
+        }
+
+        Ok(events)
+    }
+}
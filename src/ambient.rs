@@ -0,0 +1,15 @@
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AmbientLightInfo {
+    /// Sensor model identifier.
+    pub sensor_type: u8,
+    /// Whether readings are skewed by CPU heat and need compensation.
+    pub cpu_affected: bool,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AmbientLightParams {
+    /// Raw-to-lux scale applied by the firmware.
+    pub gain: u16,
+    /// Raw offset subtracted before scaling.
+    pub offset: u16,
+}
@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, HashMap};
+#[cfg(target_os = "macos")]
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+#[cfg(target_os = "macos")]
+use crate::io_connect_t;
+use crate::{
+    conversions::{SMCType, TYPE_U32},
+    keys::consts::KEY_COUNT,
+    DataType, KeyAttributes, KeyInfo, Platform, PowerLimits, SMCBytes, SMCError, SMCKey, Snapshot,
+};
+
+use four_char_code::FourCharCode;
+
+pub trait SMCBackend: Send + Sync {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError>;
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError>;
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError>;
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError>;
+
+    fn read_many(&self, keys: &[SMCKey]) -> Vec<Result<SMCBytes, SMCError>> {
+        keys.iter().map(|key| self.read(*key)).collect()
+    }
+
+    fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        Err(SMCError::UnsupportedFeature)
+    }
+
+    fn can_write(&self) -> Result<(), SMCError> {
+        Ok(())
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        None
+    }
+
+    #[cfg(target_os = "macos")]
+    fn as_raw(&self) -> Option<io_connect_t> {
+        None
+    }
+
+    #[cfg(target_os = "macos")]
+    fn detach(&self) -> Option<io_connect_t> {
+        None
+    }
+
+    /// IOService name and index this backend is connected to, if any.
+    #[cfg(target_os = "macos")]
+    fn service(&self) -> Option<(&CStr, usize)> {
+        None
+    }
+}
+
+impl<B: SMCBackend + ?Sized> SMCBackend for Box<B> {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        (**self).key_info(key)
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        (**self).read(key)
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        (**self).write(key, bytes)
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        (**self).key_at_index(index)
+    }
+
+    fn read_many(&self, keys: &[SMCKey]) -> Vec<Result<SMCBytes, SMCError>> {
+        (**self).read_many(keys)
+    }
+
+    fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        (**self).power_limits()
+    }
+
+    fn can_write(&self) -> Result<(), SMCError> {
+        (**self).can_write()
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        (**self).platform()
+    }
+
+    #[cfg(target_os = "macos")]
+    fn as_raw(&self) -> Option<io_connect_t> {
+        (**self).as_raw()
+    }
+
+    #[cfg(target_os = "macos")]
+    fn detach(&self) -> Option<io_connect_t> {
+        (**self).detach()
+    }
+
+    #[cfg(target_os = "macos")]
+    fn service(&self) -> Option<(&CStr, usize)> {
+        (**self).service()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    info: KeyInfo,
+    bytes: SMCBytes,
+    result: Option<u8>,
+}
+
+// Reads and writes are checked against the key's attributes and size the way
+// the driver does, and `fail_with` makes a key report an SMC result code.
+#[derive(Default)]
+pub struct MemoryBackend {
+    keys: Mutex<BTreeMap<FourCharCode, Entry>>,
+    platform: Option<Platform>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> MemoryBackend {
+        Default::default()
+    }
+
+    pub fn with_platform(platform: Platform) -> MemoryBackend {
+        MemoryBackend {
+            platform: Some(platform),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_map(
+        map: HashMap<FourCharCode, (DataType, Vec<u8>)>,
+    ) -> Result<MemoryBackend, SMCError> {
+        let backend = MemoryBackend::new();
+        for (key, (data_type, data)) in map {
+            backend.insert_bytes(key, data_type, &data)?;
+        }
+        Ok(backend)
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot) -> MemoryBackend {
+        let backend = MemoryBackend::new();
+        for entry in snapshot {
+            let bytes = entry.value.map(|val| *val.bytes()).unwrap_or_default();
+            backend.insert_raw(entry.key, entry.info, bytes);
+        }
+        backend
+    }
+
+    pub fn insert<T: SMCType>(
+        &self,
+        key: FourCharCode,
+        data_type: DataType,
+        value: T,
+    ) -> Result<(), SMCError> {
+        let info = KeyInfo {
+            data_type,
+            attributes: KeyAttributes::READ | KeyAttributes::WRITE,
+        };
+        self.insert_raw(key, info, value.to_smc(data_type)?);
+        Ok(())
+    }
+
+    pub fn insert_bytes(
+        &self,
+        key: FourCharCode,
+        data_type: DataType,
+        data: &[u8],
+    ) -> Result<(), SMCError> {
+        let mut bytes = SMCBytes::default();
+        if data.len() != data_type.size as usize || data.len() > bytes.0.len() {
+            return Err(SMCError::SizeMismatch {
+                key,
+                expected: data_type.size,
+                actual: data.len() as u32,
+            });
+        }
+        bytes.0[..data.len()].copy_from_slice(data);
+        let info = KeyInfo {
+            data_type,
+            attributes: KeyAttributes::READ | KeyAttributes::WRITE,
+        };
+        self.insert_raw(key, info, bytes);
+        Ok(())
+    }
+
+    pub fn insert_raw(&self, key: FourCharCode, info: KeyInfo, bytes: SMCBytes) {
+        self.keys.lock().unwrap().insert(
+            key,
+            Entry {
+                info,
+                bytes,
+                result: None,
+            },
+        );
+    }
+
+    pub fn remove(&self, key: FourCharCode) -> bool {
+        self.keys.lock().unwrap().remove(&key).is_some()
+    }
+
+    pub fn set_attributes(&self, key: FourCharCode, attributes: KeyAttributes) -> bool {
+        self.update(key, |entry| entry.info.attributes = attributes)
+    }
+
+    pub fn fail_with(&self, key: FourCharCode, smc_result: u8) -> bool {
+        self.update(key, |entry| entry.result = Some(smc_result))
+    }
+
+    pub fn clear_failure(&self, key: FourCharCode) -> bool {
+        self.update(key, |entry| entry.result = None)
+    }
+
+    pub fn data(&self, key: FourCharCode) -> Option<Vec<u8>> {
+        self.keys.lock().unwrap().get(&key).map(|entry| {
+            let len = std::cmp::min(entry.info.data_type.size as usize, entry.bytes.0.len());
+            entry.bytes.0[..len].to_vec()
+        })
+    }
+
+    fn update<F: FnOnce(&mut Entry)>(&self, key: FourCharCode, f: F) -> bool {
+        match self.keys.lock().unwrap().get_mut(&key) {
+            Some(entry) => {
+                f(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get(&self, key: FourCharCode) -> Result<Entry, SMCError> {
+        let keys = self.keys.lock().unwrap();
+        if let Some(entry) = keys.get(&key) {
+            return match entry.result {
+                Some(smc_res) => Err(SMCError::from_smc_result(key, 0, smc_res)),
+                None => Ok(*entry),
+            };
+        }
+
+        if key == KEY_COUNT {
+            let mut bytes = SMCBytes::default();
+            bytes.0[..4].copy_from_slice(&(keys.len() as u32).to_be_bytes());
+            Ok(Entry {
+                info: KeyInfo {
+                    data_type: DataType {
+                        id: TYPE_U32,
+                        size: 4,
+                    },
+                    attributes: KeyAttributes::READ,
+                },
+                bytes,
+                result: None,
+            })
+        } else {
+            Err(SMCError::KeyNotFound(key))
+        }
+    }
+}
+
+impl SMCBackend for MemoryBackend {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        Ok(self.get(key)?.info)
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        let entry = self.get(key.code)?;
+        if !entry.info.attributes.is_readable() {
+            return Err(SMCError::NotReadable(key.code));
+        }
+        if key.info.size != entry.info.data_type.size {
+            return Err(SMCError::KeySizeMismatch(key.code));
+        }
+
+        Ok(entry.bytes)
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        let entry = self.get(key.code)?;
+        if !entry.info.attributes.is_writable() {
+            return Err(SMCError::NotWritable(key.code));
+        }
+        if key.info.size != entry.info.data_type.size {
+            return Err(SMCError::KeySizeMismatch(key.code));
+        }
+
+        self.update(key.code, |entry| entry.bytes = bytes);
+        Ok(())
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        match self.keys.lock().unwrap().keys().nth(index as usize) {
+            Some(key) => Ok(*key),
+            None => Err(SMCError::KeyIndexRange),
+        }
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::SMC;
+
+    use four_char_code::four_char_code;
+
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+    const UI16: DataType = DataType {
+        id: four_char_code!("ui16"),
+        size: 2,
+    };
+
+    fn backend() -> MemoryBackend {
+        let mut map = HashMap::new();
+        map.insert(four_char_code!("FS! "), (UI16, vec![0, 1]));
+        map.insert(four_char_code!("BSIn"), (UI8, vec![3]));
+        MemoryBackend::from_map(map).unwrap()
+    }
+
+    #[test]
+    fn insert_bytes_checks_size() {
+        let backend = MemoryBackend::new();
+        let key = four_char_code!("FS! ");
+
+        for data in &[&[0_u8][..], &[0, 0, 0, 1][..]] {
+            match backend.insert_bytes(key, UI16, data) {
+                Err(SMCError::SizeMismatch {
+                    expected, actual, ..
+                }) => assert_eq!((expected, actual), (2, data.len() as u32)),
+                res => panic!("unexpected result: {:?}", res),
+            }
+        }
+        assert!(backend.data(key).is_none());
+
+        backend.insert_bytes(key, UI16, &[0, 1]).unwrap();
+        assert_eq!(backend.data(key).unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let backend = backend();
+        let key = SMCKey {
+            code: four_char_code!("FS! "),
+            info: UI8,
+        };
+
+        match backend.write(key, SMCBytes::default()) {
+            Err(SMCError::KeySizeMismatch(code)) => assert_eq!(code, key.code),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert_eq!(backend.data(key.code).unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn attributes_and_result_codes() {
+        let backend = backend();
+        backend.set_attributes(four_char_code!("BSIn"), KeyAttributes::READ);
+        backend.fail_with(four_char_code!("FS! "), 0x85);
+        let smc = SMC::with_backend(backend);
+
+        assert_eq!(smc.keys_len().unwrap(), 2);
+        assert_eq!(smc.read_key::<u8>(four_char_code!("BSIn")).unwrap(), 3);
+        match smc.read_key::<u16>(four_char_code!("FS! ")) {
+            Err(err) => match err.root() {
+                SMCError::NotReadable(_) => (),
+                err => panic!("unexpected error: {:?}", err),
+            },
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn errors_carry_operation() {
+        let backend = backend();
+        backend.set_attributes(four_char_code!("BSIn"), KeyAttributes::READ);
+        let smc = SMC::with_backend(backend);
+        smc.set_write_policy(crate::WritePolicy::allow_all());
+
+        let err = smc.write_key(four_char_code!("BSIn"), 1_u8).unwrap_err();
+        assert_eq!(err.operation(), Some(crate::Operation::Write));
+        assert_eq!(err.code(), Some(four_char_code!("BSIn")));
+        match err.kind() {
+            SMCError::NotWritable(_) => (),
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        let err = smc.write_key(four_char_code!("TC0P"), 1_u8).unwrap_err();
+        assert_eq!(err.operation(), Some(crate::Operation::Write));
+        assert!(err.is_not_found());
+    }
+
+    struct Unsorted {
+        inner: MemoryBackend,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl SMCBackend for Unsorted {
+        fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+            self.inner.key_info(key)
+        }
+
+        fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+            self.inner.read(key)
+        }
+
+        fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+            self.inner.write(key, bytes)
+        }
+
+        fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let len = self.inner.keys.lock().unwrap().len() as u32;
+            self.inner.key_at_index(len - 1 - index)
+        }
+    }
+
+    #[test]
+    fn key_index_caches_unsorted_table() {
+        let inner = MemoryBackend::new();
+        for key in &["AC-W", "BCLM", "F0Ac", "FNum", "TC0P"] {
+            inner.insert(FourCharCode::from(*key), UI8, 0_u8).unwrap();
+        }
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let smc = SMC::with_backend(Unsorted {
+            inner,
+            lookups: lookups.clone(),
+        });
+        let count = || lookups.load(Ordering::SeqCst);
+
+        assert_eq!(smc.key_index(four_char_code!("BCLM")).unwrap(), 3);
+        let first = count();
+        assert_eq!(smc.key_index(four_char_code!("TC0P")).unwrap(), 0);
+        assert_eq!(smc.key_index(four_char_code!("AC-W")).unwrap(), 4);
+        // Two binary searches of at most three probes each; without the
+        // cached map each miss would also walk the table.
+        assert!(count() - first <= 6);
+
+        smc.clear_key_info_cache();
+        assert_eq!(smc.key_index(four_char_code!("FNum")).unwrap(), 1);
+    }
+}
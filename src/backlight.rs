@@ -0,0 +1,58 @@
+use crate::{Platform, SMCError, SMC};
+
+use four_char_code::four_char_code;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyboardBacklight {
+    pub brightness: u8,
+    pub flags: u8,
+}
+
+impl KeyboardBacklight {
+    pub fn percent(&self) -> u8 {
+        ((u16::from(self.brightness) * 100 + 127) / 255) as u8
+    }
+}
+
+fn brightness_from_percent(percent: u8) -> u8 {
+    ((u16::from(std::cmp::min(percent, 100)) * 255 + 50) / 100) as u8
+}
+
+impl SMC {
+    pub fn keyboard_backlight_state(&self) -> Result<KeyboardBacklight, SMCError> {
+        if self.platform() != Some(Platform::AppleSilicon) {
+            match self.0.read_key(four_char_code!("LKSB")) {
+                Err(ref err) if err.is_not_found() => (),
+                res => return res,
+            }
+        }
+
+        let (_, val) = self.read_key_raw(four_char_code!("KSBL"))?;
+        let data = val.data();
+        Ok(KeyboardBacklight {
+            brightness: data.first().cloned().unwrap_or(0),
+            flags: data.get(1).cloned().unwrap_or(0),
+        })
+    }
+
+    pub fn keyboard_backlight(&self) -> Result<u8, SMCError> {
+        Ok(self.keyboard_backlight_state()?.percent())
+    }
+
+    pub fn set_keyboard_backlight(&self, percent: u8) -> Result<(), SMCError> {
+        let state = KeyboardBacklight {
+            brightness: brightness_from_percent(percent),
+            flags: 0,
+        };
+
+        if self.platform() != Some(Platform::AppleSilicon) {
+            match self.0.write_key(four_char_code!("LKSB"), state) {
+                Err(ref err) if err.is_not_found() => (),
+                res => return res,
+            }
+        }
+
+        self.0
+            .write_key_raw(four_char_code!("KSBL"), &[state.brightness, state.flags])
+    }
+}
@@ -0,0 +1,247 @@
+extern crate four_char_code;
+extern crate smc;
+
+#[cfg(target_os = "macos")]
+mod cli {
+    use std::env;
+    use std::process;
+    use std::thread;
+    use std::time::Duration;
+
+    use four_char_code::{four_char_code, FourCharCode};
+    use smc::{keydb, DecodedValue, SMCError, SMCString, SMCVal, WritePolicy, SMC};
+
+    const USAGE: &str = "usage: smc <command> [args]
+
+commands:
+    list                    list every key with its type and value
+    read <key>              read a key
+    write [--force] <key> <value>
+                            write a key; --force allows keys outside the
+                            default write policy
+    fans                    show fans status
+    temps                   show temperature sensors
+    watch <key>... [-i ms]  print keys every interval (default 1000ms)";
+
+    fn parse_key(key: &str) -> Result<FourCharCode, String> {
+        if key.len() == 4 && key.is_ascii() {
+            Ok(FourCharCode::from(key))
+        } else {
+            Err(format!("invalid key {:?}", key))
+        }
+    }
+
+    fn format_value(val: &SMCVal) -> String {
+        match val.decode() {
+            DecodedValue::Str(s) => format!("{:?}", s),
+            value => value.to_string(),
+        }
+    }
+
+    fn print_key(smc: &SMC, key: FourCharCode) -> Result<(), SMCError> {
+        let (data_type, val) = smc.read_key_raw(key)?;
+        println!(
+            "  {}  [{}/{}]  {}",
+            key.to_string(),
+            data_type.id.to_string(),
+            data_type.size,
+            format_value(&val)
+        );
+        Ok(())
+    }
+
+    fn list(smc: &SMC) -> Result<(), String> {
+        let snapshot = smc.snapshot().map_err(|e| e.to_string())?;
+        for entry in snapshot.iter() {
+            let value = match entry.value {
+                Some(ref val) => format_value(val),
+                None => "-".to_string(),
+            };
+            let description = match keydb::lookup(entry.key) {
+                Some(desc) => match desc.unit {
+                    Some(unit) => format!("  {} ({})", desc.description, unit),
+                    None => format!("  {}", desc.description),
+                },
+                None => String::new(),
+            };
+            println!(
+                "  {}  [{}/{}]  {}{}",
+                entry.key.to_string(),
+                entry.info.data_type.id.to_string(),
+                entry.info.data_type.size,
+                value,
+                description
+            );
+        }
+        Ok(())
+    }
+
+    fn write(smc: &SMC, key: FourCharCode, value: &str, force: bool) -> Result<(), String> {
+        smc.can_write().map_err(|e| e.to_string())?;
+        let info = smc.key_info_full(key).map_err(|e| e.to_string())?;
+        let id = info.data_type.id;
+        if force {
+            smc.set_write_policy(WritePolicy::allow_all());
+        } else if !smc.write_policy().is_allowed(key) {
+            return Err(format!(
+                "{} is outside the default write policy, use --force to write it anyway",
+                key.to_string()
+            ));
+        }
+
+        macro_rules! parse {
+            ( $t:ty ) => {
+                value
+                    .parse::<$t>()
+                    .map_err(|e| format!("invalid value {:?}: {}", value, e))?
+            };
+        }
+
+        let res = if id == four_char_code!("flag") {
+            let flag = match value {
+                "1" | "true" | "on" => true,
+                "0" | "false" | "off" => false,
+                _ => return Err(format!("invalid flag value {:?}", value)),
+            };
+            smc.try_write_key(key, flag)
+        } else if id == four_char_code!("ui8 ") {
+            smc.try_write_key(key, parse!(u8))
+        } else if id == four_char_code!("ui16") {
+            smc.try_write_key(key, parse!(u16))
+        } else if id == four_char_code!("ui32") {
+            smc.try_write_key(key, parse!(u32))
+        } else if id == four_char_code!("ui64") {
+            smc.try_write_key(key, parse!(u64))
+        } else if id == four_char_code!("si8 ") {
+            smc.try_write_key(key, parse!(i8))
+        } else if id == four_char_code!("si16") {
+            smc.try_write_key(key, parse!(i16))
+        } else if id == four_char_code!("si32") {
+            smc.try_write_key(key, parse!(i32))
+        } else if id == four_char_code!("si64") {
+            smc.try_write_key(key, parse!(i64))
+        } else if id == four_char_code!("fpe2")
+            || id == four_char_code!("sp78")
+            || id == four_char_code!("flt ")
+            || id == four_char_code!("ioft")
+        {
+            smc.try_write_key(key, parse!(f64))
+        } else if id == four_char_code!("ch8*") {
+            smc.try_write_key(key, SMCString::from(value))
+        } else {
+            return Err(format!("cannot write keys of type {}", id.to_string()));
+        };
+
+        res.map_err(|e| e.to_string())
+    }
+
+    fn fans(smc: &SMC) -> Result<(), String> {
+        for fan in smc.fans().map_err(|e| e.to_string())? {
+            let info = fan.info().map_err(|e| e.to_string())?;
+            let target = match info.target_speed {
+                Some(target) => format!(", target {:.0}", target),
+                None => String::new(),
+            };
+            println!(
+                "  {} {:?}: {:.0} rpm (min {:.0}, max {:.0}{}, {:?})",
+                info.id,
+                info.name,
+                info.current_speed,
+                info.min_speed,
+                info.max_speed,
+                target,
+                info.mode
+            );
+        }
+        Ok(())
+    }
+
+    fn temps(smc: &SMC) -> Result<(), String> {
+        for sensor in smc.temperature_sensors().map_err(|e| e.to_string())? {
+            match sensor.read(smc) {
+                Ok(temp) => println!(
+                    "  {}  {:<24}  {:.1}",
+                    sensor.key().to_string(),
+                    sensor.label(),
+                    temp
+                ),
+                Err(err) => println!(
+                    "  {}  {:<24}  {}",
+                    sensor.key().to_string(),
+                    sensor.label(),
+                    err
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn watch(smc: &SMC, args: &[String]) -> Result<(), String> {
+        let mut keys = Vec::new();
+        let mut interval = 1000;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-i" {
+                interval = match args.next().map(|v| v.parse::<u64>()) {
+                    Some(Ok(v)) => v,
+                    _ => return Err("invalid interval".to_string()),
+                };
+            } else {
+                keys.push(parse_key(arg)?);
+            }
+        }
+
+        if keys.is_empty() {
+            return Err(USAGE.to_string());
+        }
+
+        loop {
+            for key in keys.iter() {
+                print_key(smc, *key).map_err(|e| e.to_string())?;
+            }
+            println!();
+            thread::sleep(Duration::from_millis(interval));
+        }
+    }
+
+    fn run(args: &[String]) -> Result<(), String> {
+        if args.is_empty() {
+            return Err(USAGE.to_string());
+        }
+
+        let smc = SMC::new().map_err(|e| e.to_string())?;
+
+        match (args[0].as_str(), &args[1..]) {
+            ("list", []) => list(&smc),
+            ("read", [key]) => print_key(&smc, parse_key(key)?).map_err(|e| e.to_string()),
+            ("write", [key, value]) => write(&smc, parse_key(key)?, value, false),
+            ("write", [flag, key, value]) if flag == "--force" => {
+                write(&smc, parse_key(key)?, value, true)
+            }
+            ("fans", []) => fans(&smc),
+            ("temps", []) => temps(&smc),
+            ("watch", rest) => watch(&smc, rest),
+            _ => Err(USAGE.to_string()),
+        }
+    }
+
+    pub fn main() {
+        let args: Vec<String> = env::args().skip(1).collect();
+        if let Err(err) = run(&args) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn main() {
+    cli::main()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+    eprintln!("smc is only supported on macOS");
+    std::process::exit(1);
+}
@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{IOKitBackend, RetryPolicy, SMCError, SMCRepr, WritePolicy, SMC};
+
+#[derive(Debug, Clone)]
+pub struct SMCBuilder {
+    service: String,
+    service_index: usize,
+    open_type: u32,
+    cache_key_info: bool,
+    reconnect: bool,
+    retry: RetryPolicy,
+    write_policy: WritePolicy,
+    journal: bool,
+    rate_limit: Option<u32>,
+}
+
+impl SMCBuilder {
+    pub fn new() -> SMCBuilder {
+        SMCBuilder {
+            service: "AppleSMC".to_string(),
+            service_index: 0,
+            open_type: 0,
+            cache_key_info: true,
+            reconnect: false,
+            retry: RetryPolicy::none(),
+            write_policy: WritePolicy::default(),
+            journal: false,
+            rate_limit: None,
+        }
+    }
+
+    pub fn service<S: Into<String>>(mut self, service: S) -> SMCBuilder {
+        self.service = service.into();
+        self
+    }
+
+    pub fn service_index(mut self, service_index: usize) -> SMCBuilder {
+        self.service_index = service_index;
+        self
+    }
+
+    pub fn open_type(mut self, open_type: u32) -> SMCBuilder {
+        self.open_type = open_type;
+        self
+    }
+
+    pub fn cache_key_info(mut self, cache_key_info: bool) -> SMCBuilder {
+        self.cache_key_info = cache_key_info;
+        self
+    }
+
+    pub fn reconnect(mut self, reconnect: bool) -> SMCBuilder {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> SMCBuilder {
+        self.retry = retry;
+        self
+    }
+
+    pub fn write_policy(mut self, write_policy: WritePolicy) -> SMCBuilder {
+        self.write_policy = write_policy;
+        self
+    }
+
+    pub fn journal(mut self, journal: bool) -> SMCBuilder {
+        self.journal = journal;
+        self
+    }
+
+    pub fn rate_limit(mut self, max_calls_per_sec: u32) -> SMCBuilder {
+        self.rate_limit = Some(max_calls_per_sec);
+        self
+    }
+
+    pub fn open(self) -> Result<SMC, SMCError> {
+        let backend =
+            IOKitBackend::with_service_index(&self.service, self.service_index, self.open_type)?
+                .with_reconnect(self.reconnect)
+                .with_retry(self.retry);
+        let mut repr = SMCRepr::new(backend);
+        repr.cache_key_info = self.cache_key_info;
+        repr.write_policy = Mutex::new(self.write_policy);
+        repr.rate_limiter.set_limit(self.rate_limit);
+        let smc = SMC(Arc::new(repr));
+        smc.set_journal(self.journal);
+        Ok(smc)
+    }
+}
+
+impl Default for SMCBuilder {
+    fn default() -> SMCBuilder {
+        SMCBuilder::new()
+    }
+}
+
+impl SMC {
+    pub fn builder() -> SMCBuilder {
+        SMCBuilder::new()
+    }
+}
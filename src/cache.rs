@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{conversions::SMCType, SMCError, SMCKey, SMCRepr, SMCVal, SMC};
+
+use four_char_code::FourCharCode;
+
+pub struct CachedSMC {
+    smc: SMC,
+    ttl: Duration,
+    ttls: HashMap<FourCharCode, Duration>,
+    cache: Mutex<HashMap<FourCharCode, (Instant, SMCVal)>>,
+}
+
+impl CachedSMC {
+    pub fn new(smc: SMC, ttl: Duration) -> CachedSMC {
+        CachedSMC {
+            smc,
+            ttl,
+            ttls: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_key_ttl(mut self, key: FourCharCode, ttl: Duration) -> CachedSMC {
+        self.set_key_ttl(key, ttl);
+        self
+    }
+
+    pub fn set_key_ttl(&mut self, key: FourCharCode, ttl: Duration) {
+        self.ttls.insert(key, ttl);
+    }
+
+    #[inline]
+    pub fn smc(&self) -> &SMC {
+        &self.smc
+    }
+
+    pub fn ttl(&self, key: FourCharCode) -> Duration {
+        match self.ttls.get(&key) {
+            Some(ttl) => *ttl,
+            None => self.ttl,
+        }
+    }
+
+    pub fn read_key_raw(&self, key: FourCharCode) -> Result<SMCVal, SMCError> {
+        let now = Instant::now();
+        if let Some((at, val)) = self.cache.lock().unwrap().get(&key) {
+            if now.duration_since(*at) < self.ttl(key) {
+                return Ok(*val);
+            }
+        }
+
+        let (_, val) = self.smc.read_key_raw(key)?;
+        self.cache.lock().unwrap().insert(key, (now, val));
+        Ok(val)
+    }
+
+    pub fn read_key<T: SMCType>(&self, key: FourCharCode) -> Result<T, SMCError> {
+        let val = self.read_key_raw(key)?;
+        SMCRepr::check_size(SMCKey {
+            code: key,
+            info: val.data_type(),
+        })?;
+        T::from_smc(val.data_type(), *val.bytes()).map_err(|e| e.for_key(key))
+    }
+
+    pub fn invalidate(&self, key: FourCharCode) {
+        self.cache.lock().unwrap().remove(&key);
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+impl SMC {
+    pub fn cached(&self, ttl: Duration) -> CachedSMC {
+        CachedSMC::new(self.clone(), ttl)
+    }
+}
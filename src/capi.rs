@@ -0,0 +1,345 @@
+//! C bindings, declared in `include/smc.h`.
+//!
+//! The crate only builds an rlib by default. Build a C library with
+//!
+//! ```sh
+//! cargo rustc --release --features capi --crate-type cdylib
+//! cargo rustc --release --features capi --crate-type staticlib
+//! ```
+
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+pub const SMC_OK: c_int = 0;
+pub const SMC_ERR_INVALID_ARGUMENT: c_int = 1;
+pub const SMC_ERR_BUFFER_TOO_SMALL: c_int = 2;
+pub const SMC_ERR_FAILED_TO_OPEN: c_int = 3;
+pub const SMC_ERR_KEY_NOT_FOUND: c_int = 4;
+pub const SMC_ERR_NOT_READABLE: c_int = 5;
+pub const SMC_ERR_NOT_WRITABLE: c_int = 6;
+pub const SMC_ERR_NOT_PRIVILEGED: c_int = 7;
+pub const SMC_ERR_WRITE_DENIED: c_int = 8;
+pub const SMC_ERR_UNSUPPORTED: c_int = 9;
+pub const SMC_ERR_PANIC: c_int = 10;
+pub const SMC_ERR_OTHER: c_int = 255;
+
+#[allow(non_camel_case_types)]
+pub struct smc_t(SMC);
+
+fn error_code(err: &SMCError) -> c_int {
+    match err.root() {
+        SMCError::FailedToOpen | SMCError::DriverNotFound => SMC_ERR_FAILED_TO_OPEN,
+        SMCError::KeyNotFound(_) => SMC_ERR_KEY_NOT_FOUND,
+        SMCError::NotReadable(_) => SMC_ERR_NOT_READABLE,
+        SMCError::NotWritable(_) => SMC_ERR_NOT_WRITABLE,
+        SMCError::NotPrivileged => SMC_ERR_NOT_PRIVILEGED,
+        SMCError::WriteDenied(_) => SMC_ERR_WRITE_DENIED,
+        SMCError::UnsupportedFeature | SMCError::Unsupported => SMC_ERR_UNSUPPORTED,
+        SMCError::InvalidArgument | SMCError::BadArgument => SMC_ERR_INVALID_ARGUMENT,
+        _ => SMC_ERR_OTHER,
+    }
+}
+
+// Panics (e.g. a poisoned lock) must not unwind into the caller's frames.
+fn guard<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(SMC_ERR_PANIC)
+}
+
+fn status(res: Result<(), SMCError>) -> c_int {
+    match res {
+        Ok(()) => SMC_OK,
+        Err(err) => error_code(&err),
+    }
+}
+
+/// # Safety
+///
+/// `out` must be a valid pointer to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn smc_open(out: *mut *mut smc_t) -> c_int {
+    guard(|| {
+        if out.is_null() {
+            return SMC_ERR_INVALID_ARGUMENT;
+        }
+
+        #[cfg(target_os = "macos")]
+        let res = SMC::new();
+        #[cfg(not(target_os = "macos"))]
+        let res: Result<SMC, SMCError> = Err(SMCError::Unsupported);
+
+        match res {
+            Ok(smc) => {
+                *out = Box::into_raw(Box::new(smc_t(smc)));
+                SMC_OK
+            }
+            Err(err) => {
+                *out = ptr::null_mut();
+                error_code(&err)
+            }
+        }
+    })
+}
+
+/// # Safety
+///
+/// `smc` must be null or a handle returned by `smc_open` that was not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn smc_close(smc: *mut smc_t) {
+    if !smc.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(smc))));
+    }
+}
+
+/// # Safety
+///
+/// `smc` must be a live handle, `data_type` null or writable, `len` writable
+/// and `buf` valid for `*len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn smc_read_key(
+    smc: *const smc_t,
+    key: u32,
+    data_type: *mut u32,
+    buf: *mut u8,
+    len: *mut usize,
+) -> c_int {
+    guard(|| {
+        if smc.is_null() || len.is_null() || (buf.is_null() && *len != 0) {
+            return SMC_ERR_INVALID_ARGUMENT;
+        }
+
+        let (_, val) = match (*smc).0.read_key_raw(FourCharCode(key)) {
+            Ok(res) => res,
+            Err(err) => return error_code(&err),
+        };
+
+        if !data_type.is_null() {
+            *data_type = val.data_type().id.to_u32();
+        }
+
+        let data = val.data();
+        let capacity = *len;
+        *len = data.len();
+        if capacity < data.len() {
+            return SMC_ERR_BUFFER_TOO_SMALL;
+        }
+        if !data.is_empty() {
+            slice::from_raw_parts_mut(buf, data.len()).copy_from_slice(data);
+        }
+
+        SMC_OK
+    })
+}
+
+/// Writes exactly `len` bytes, which must match the key's size.
+///
+/// # Safety
+///
+/// `smc` must be a live handle and `buf` valid for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn smc_write_key(
+    smc: *const smc_t,
+    key: u32,
+    buf: *const u8,
+    len: usize,
+) -> c_int {
+    guard(|| {
+        if smc.is_null() || (buf.is_null() && len != 0) {
+            return SMC_ERR_INVALID_ARGUMENT;
+        }
+
+        let key = FourCharCode(key);
+        let info = match (*smc).0.key_info_full(key) {
+            Ok(info) => info,
+            Err(err) => return error_code(&err),
+        };
+        if len != info.data_type.size as usize {
+            return SMC_ERR_INVALID_ARGUMENT;
+        }
+
+        let data: &[u8] = if len == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(buf, len)
+        };
+        status((*smc).0 .0.write_key_raw(key, data))
+    })
+}
+
+/// # Safety
+///
+/// `smc` must be a live handle, `len` writable and `keys` valid for `*len`
+/// elements.
+#[no_mangle]
+pub unsafe extern "C" fn smc_list_keys(
+    smc: *const smc_t,
+    keys: *mut u32,
+    len: *mut usize,
+) -> c_int {
+    guard(|| {
+        if smc.is_null() || len.is_null() || (keys.is_null() && *len != 0) {
+            return SMC_ERR_INVALID_ARGUMENT;
+        }
+
+        let list = match (*smc).0.keys() {
+            Ok(list) => list,
+            Err(err) => return error_code(&err),
+        };
+
+        let capacity = *len;
+        *len = list.len();
+        if capacity < list.len() {
+            return SMC_ERR_BUFFER_TOO_SMALL;
+        }
+        if !list.is_empty() {
+            let out = slice::from_raw_parts_mut(keys, list.len());
+            for (out, key) in out.iter_mut().zip(list) {
+                *out = key.to_u32();
+            }
+        }
+
+        SMC_OK
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn smc_strerror(code: c_int) -> *const c_char {
+    let msg: &'static [u8] = match code {
+        SMC_OK => b"success\0",
+        SMC_ERR_INVALID_ARGUMENT => b"invalid argument\0",
+        SMC_ERR_BUFFER_TOO_SMALL => b"buffer too small\0",
+        SMC_ERR_FAILED_TO_OPEN => b"failed to open SMC\0",
+        SMC_ERR_KEY_NOT_FOUND => b"key not found\0",
+        SMC_ERR_NOT_READABLE => b"key not readable\0",
+        SMC_ERR_NOT_WRITABLE => b"key not writable\0",
+        SMC_ERR_NOT_PRIVILEGED => b"not privileged\0",
+        SMC_ERR_WRITE_DENIED => b"write denied by policy\0",
+        SMC_ERR_UNSUPPORTED => b"unsupported\0",
+        SMC_ERR_PANIC => b"internal panic\0",
+        _ => b"unknown error\0",
+    };
+    msg.as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MemoryBackend};
+
+    const SOURCE: &str = include_str!("capi.rs");
+    const HEADER: &str = include_str!("../include/smc.h");
+
+    // `<anything> name(params...` -> (name, parameter count)
+    fn signature(decl: &str) -> Option<(String, usize)> {
+        let open = decl.find('(')?;
+        let close = decl.find(')')?;
+        let name = decl[..open]
+            .split_whitespace()
+            .last()?
+            .trim_start_matches('*');
+        let params = &decl[open + 1..close];
+        let count = params
+            .split(',')
+            .filter(|param| !param.trim().is_empty() && param.trim() != "void")
+            .count();
+        Some((name.to_string(), count))
+    }
+
+    fn source_functions() -> Vec<(String, usize)> {
+        let mut res: Vec<(String, usize)> = SOURCE
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .filter_map(signature)
+            .collect();
+        res.sort();
+        res
+    }
+
+    fn header_functions() -> Vec<(String, usize)> {
+        let mut code = String::new();
+        let mut rest = HEADER;
+        while let Some(start) = rest.find("/*") {
+            code.push_str(&rest[..start]);
+            rest = &rest[start + rest[start..].find("*/").unwrap() + 2..];
+        }
+        code.push_str(rest);
+
+        let code: String = code
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut res: Vec<(String, usize)> = code
+            .split(';')
+            .filter_map(signature)
+            .filter(|(name, _)| name.starts_with("smc_"))
+            .collect();
+        res.sort();
+        res
+    }
+
+    fn constants(text: &str, prefix: &str, sep: &str) -> Vec<(String, String)> {
+        let mut res: Vec<(String, String)> = text
+            .lines()
+            .filter(|line| line.starts_with(prefix))
+            .filter_map(|line| {
+                let mut parts = line[prefix.len()..].splitn(2, sep);
+                let name = parts.next()?.trim();
+                let value = parts.next()?.trim().trim_end_matches(';');
+                if name.starts_with("SMC_") {
+                    Some((name.to_string(), value.to_string()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn header_matches_exports() {
+        assert!(!source_functions().is_empty());
+        assert_eq!(header_functions(), source_functions());
+        assert_eq!(
+            constants(HEADER, "#define ", " "),
+            constants(SOURCE, "pub const ", ": c_int =")
+        );
+    }
+
+    #[test]
+    fn write_requires_exact_size() {
+        let backend = MemoryBackend::new();
+        let ui16 = DataType {
+            id: FourCharCode::from("ui16"),
+            size: 2,
+        };
+        backend
+            .insert(FourCharCode::from("F0Tg"), ui16, 0_u16)
+            .unwrap();
+        let smc = smc_t(SMC::with_backend(backend));
+        let key = FourCharCode::from("F0Tg").to_u32();
+        let buf = [0x12_u8; 40];
+
+        unsafe {
+            assert_eq!(
+                smc_write_key(&smc, key, buf.as_ptr(), 1),
+                SMC_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(
+                smc_write_key(&smc, key, buf.as_ptr(), 40),
+                SMC_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(smc_write_key(&smc, key, buf.as_ptr(), 2), SMC_OK);
+        }
+        assert_eq!(
+            smc.0.read_key::<u16>(FourCharCode::from("F0Tg")).unwrap(),
+            0x1212
+        );
+    }
+}
@@ -1,13 +1,15 @@
-use crate::{DataType, SMCBytes};
+use crate::{
+    AmbientLightInfo, AmbientLightParams, DataType, DriveBayInfo, FanDescriptor, FanLocation,
+    FanType, KeyboardBacklight, MotionSensorStatus, Revision, SMCBytes, SMCError, SMCVal,
+    ThermalLimits,
+};
 
-#[derive(Debug)]
-pub(crate) struct RawFan {
-    pub(crate) name: String,
-}
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SMCString(pub String);
 
 use four_char_code::{four_char_code, FourCharCode};
-use libc::{c_void, memcpy};
-use std::{slice, str};
+use libc::{c_void, memcpy, memset};
+use std::{fmt, ops::Deref, slice, str};
 
 pub const TYPE_FLAG: FourCharCode = four_char_code!("flag");
 pub const TYPE_I8: FourCharCode = four_char_code!("si8 ");
@@ -16,10 +18,23 @@ pub const TYPE_I16: FourCharCode = four_char_code!("si16");
 pub const TYPE_U16: FourCharCode = four_char_code!("ui16");
 pub const TYPE_I32: FourCharCode = four_char_code!("si32");
 pub const TYPE_U32: FourCharCode = four_char_code!("ui32");
+pub const TYPE_I64: FourCharCode = four_char_code!("si64");
+pub const TYPE_U64: FourCharCode = four_char_code!("ui64");
 pub const TYPE_FLT: FourCharCode = four_char_code!("flt ");
 pub const TYPE_FPE2: FourCharCode = four_char_code!("fpe2");
 pub const TYPE_SP78: FourCharCode = four_char_code!("sp78");
+pub const TYPE_IOFT: FourCharCode = four_char_code!("ioft");
+pub const TYPE_CH8: FourCharCode = four_char_code!("ch8*");
+pub const TYPE_HEX: FourCharCode = four_char_code!("hex_");
 pub const TYPE_FAN: FourCharCode = four_char_code!("{fds");
+pub const TYPE_LKB: FourCharCode = four_char_code!("{lkb");
+pub const TYPE_LKS: FourCharCode = four_char_code!("{lks");
+pub const TYPE_REV: FourCharCode = four_char_code!("{rev");
+pub const TYPE_HDI: FourCharCode = four_char_code!("{hdi");
+pub const TYPE_LIM: FourCharCode = four_char_code!("{lim");
+pub const TYPE_MSS: FourCharCode = four_char_code!("{mss");
+pub const TYPE_ALI: FourCharCode = four_char_code!("{ali");
+pub const TYPE_ALP: FourCharCode = four_char_code!("{alp");
 
 fn read_string(buffer: *const u8, max: usize) -> String {
     let len = match unsafe { slice::from_raw_parts(buffer, max) }
@@ -30,289 +45,1005 @@ fn read_string(buffer: *const u8, max: usize) -> String {
         None => max,
     };
 
-    unsafe { str::from_utf8_unchecked(slice::from_raw_parts(buffer, len)) }
+    String::from_utf8_lossy(unsafe { slice::from_raw_parts(buffer, len) })
         .trim()
         .to_string()
 }
 
-// fn write_string(buffer: *mut u8, value: &str, max: usize) {
-//     let len = if value.len() > max { max } else { value.len() };
-//     unsafe {
-//         memcpy(buffer as *mut c_void, value.as_ptr() as *const c_void, len);
-//         if max > len {
-//             memset(buffer.add(len) as *mut c_void, 32, max - len);
-//         }
-//     }
-// }
+// ch8* values longer than the key are cut at the last whole UTF-8 character
+// that fits, shorter ones are padded with spaces like the SMC does.
+fn write_string(buffer: *mut u8, value: &str, max: usize) {
+    let mut len = if value.len() > max { max } else { value.len() };
+    while !value.is_char_boundary(len) {
+        len -= 1;
+    }
+    unsafe {
+        memcpy(buffer as *mut c_void, value.as_ptr() as *const c_void, len);
+        if max > len {
+            memset(buffer.add(len) as *mut c_void, 32, max - len);
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HexBytes(pub Vec<u8>);
+
+impl HexBytes {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for HexBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for HexBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x")?;
+        for b in self.0.iter() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<u8>> for HexBytes {
+    fn from(value: Vec<u8>) -> HexBytes {
+        HexBytes(value)
+    }
+}
+
+impl<'a> From<&'a [u8]> for HexBytes {
+    fn from(value: &'a [u8]) -> HexBytes {
+        HexBytes(value.to_vec())
+    }
+}
+
+impl From<HexBytes> for Vec<u8> {
+    fn from(value: HexBytes) -> Vec<u8> {
+        value.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    pub key: Option<FourCharCode>,
+    pub type_name: &'static str,
+    pub data_type: DataType,
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Cannot convert between {} and {}/{}",
+            self.type_name,
+            self.data_type.id.to_string(),
+            self.data_type.size
+        )?;
+        if let Some(key) = self.key {
+            write!(f, " for key {}", key.to_string())?;
+        }
+        if !self.data.is_empty() {
+            write!(f, " (data: 0x")?;
+            for b in &self.data {
+                write!(f, "{:02x}", b)?;
+            }
+            write!(f, ")")?;
+        }
+        write!(f, ".")
+    }
+}
+
+impl SMCString {
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for SMCString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SMCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl From<String> for SMCString {
+    fn from(value: String) -> SMCString {
+        SMCString(value)
+    }
+}
+
+impl<'a> From<&'a str> for SMCString {
+    fn from(value: &'a str) -> SMCString {
+        SMCString(value.to_string())
+    }
+}
+
+impl From<SMCString> for String {
+    fn from(value: SMCString) -> String {
+        value.0
+    }
+}
+
+fn hex_digit(c: u8) -> Option<u32> {
+    (c as char).to_digit(16)
+}
+
+pub(crate) fn fixed_point(id: FourCharCode) -> Option<(bool, u32)> {
+    let bytes = id.to_u32().to_be_bytes();
+    let signed = match &bytes[..2] {
+        b"fp" => false,
+        b"sp" => true,
+        _ => return None,
+    };
+    let int = hex_digit(bytes[2])?;
+    let frac = hex_digit(bytes[3])?;
+
+    if int + frac + (signed as u32) == 16 {
+        Some((signed, frac))
+    } else {
+        None
+    }
+}
 
-pub trait SMCType {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes;
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Self;
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum KnownType {
+    Flag,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    Flt,
+    Ioft,
+    Fp(u8, u8),
+    Sp(u8, u8),
+    Ch8,
+    Hex,
+    Struct(FourCharCode),
+}
+
+impl KnownType {
+    pub fn from_id(id: FourCharCode) -> Option<KnownType> {
+        const SIMPLE: &[(FourCharCode, KnownType)] = &[
+            (TYPE_FLAG, KnownType::Flag),
+            (TYPE_U8, KnownType::U8),
+            (TYPE_U16, KnownType::U16),
+            (TYPE_U32, KnownType::U32),
+            (TYPE_U64, KnownType::U64),
+            (TYPE_I8, KnownType::I8),
+            (TYPE_I16, KnownType::I16),
+            (TYPE_I32, KnownType::I32),
+            (TYPE_I64, KnownType::I64),
+            (TYPE_FLT, KnownType::Flt),
+            (TYPE_IOFT, KnownType::Ioft),
+            (TYPE_CH8, KnownType::Ch8),
+            (TYPE_HEX, KnownType::Hex),
+        ];
+
+        if let Some((_, ty)) = SIMPLE.iter().find(|(ty_id, _)| *ty_id == id) {
+            return Some(*ty);
+        }
+
+        if let Some((signed, frac)) = fixed_point(id) {
+            let int = (16 - frac - signed as u32) as u8;
+            return Some(if signed {
+                KnownType::Sp(int, frac as u8)
+            } else {
+                KnownType::Fp(int, frac as u8)
+            });
+        }
+
+        if id.to_u32().to_be_bytes()[0] == b'{' {
+            Some(KnownType::Struct(id))
+        } else {
+            None
+        }
+    }
+
+    pub fn id(self) -> FourCharCode {
+        fn fixed(prefix: &[u8; 2], int: u8, frac: u8) -> FourCharCode {
+            let digit = |d: u8| std::char::from_digit(u32::from(d), 16).unwrap_or('0') as u8;
+            FourCharCode(u32::from_be_bytes([
+                prefix[0],
+                prefix[1],
+                digit(int),
+                digit(frac),
+            ]))
+        }
+
+        match self {
+            KnownType::Flag => TYPE_FLAG,
+            KnownType::U8 => TYPE_U8,
+            KnownType::U16 => TYPE_U16,
+            KnownType::U32 => TYPE_U32,
+            KnownType::U64 => TYPE_U64,
+            KnownType::I8 => TYPE_I8,
+            KnownType::I16 => TYPE_I16,
+            KnownType::I32 => TYPE_I32,
+            KnownType::I64 => TYPE_I64,
+            KnownType::Flt => TYPE_FLT,
+            KnownType::Ioft => TYPE_IOFT,
+            KnownType::Fp(int, frac) => fixed(b"fp", int, frac),
+            KnownType::Sp(int, frac) => fixed(b"sp", int, frac),
+            KnownType::Ch8 => TYPE_CH8,
+            KnownType::Hex => TYPE_HEX,
+            KnownType::Struct(id) => id,
+        }
+    }
+
+    pub fn size(self) -> Option<u32> {
+        match self {
+            KnownType::Flag | KnownType::U8 | KnownType::I8 => Some(1),
+            KnownType::U16 | KnownType::I16 | KnownType::Fp(..) | KnownType::Sp(..) => Some(2),
+            KnownType::U32 | KnownType::I32 | KnownType::Flt => Some(4),
+            KnownType::U64 | KnownType::I64 | KnownType::Ioft => Some(8),
+            KnownType::Ch8 | KnownType::Hex | KnownType::Struct(_) => None,
+        }
+    }
+
+    pub fn is_numeric(self) -> bool {
+        self.size().is_some()
+    }
+
+    pub fn is_signed(self) -> bool {
+        match self {
+            KnownType::I8
+            | KnownType::I16
+            | KnownType::I32
+            | KnownType::I64
+            | KnownType::Flt
+            | KnownType::Sp(..) => true,
+            _ => false,
+        }
+    }
+}
+
+pub(crate) fn expected_size(id: FourCharCode) -> Option<u32> {
+    KnownType::from_id(id).and_then(KnownType::size)
+}
+
+pub(crate) fn decode_number(data_type: DataType, bytes: SMCBytes) -> Option<f64> {
+    match data_type.known() {
+        Some(KnownType::Flag) => {
+            bool::from_smc(data_type, bytes)
+                .ok()
+                .map(|v| if v { 1.0 } else { 0.0 })
+        }
+        Some(KnownType::U8) | Some(KnownType::U16) | Some(KnownType::U32)
+        | Some(KnownType::U64) => u64::from_smc(data_type, bytes).ok().map(|v| v as f64),
+        Some(KnownType::I8) | Some(KnownType::I16) | Some(KnownType::I32)
+        | Some(KnownType::I64) => i64::from_smc(data_type, bytes).ok().map(|v| v as f64),
+        _ => f64::from_smc(data_type, bytes).ok(),
+    }
+}
+
+pub(crate) fn decode_string(data_type: DataType, bytes: SMCBytes) -> Option<String> {
+    SMCString::from_smc(data_type, bytes).ok().map(|s| s.0)
+}
+
+impl fmt::Display for SMCVal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.decode(), f)
+    }
+}
+
+fn type_mismatch(name: &'static str, data_type: DataType, bytes: Option<&SMCBytes>) -> SMCError {
+    let data = match bytes {
+        Some(bytes) => {
+            let len = std::cmp::min(data_type.size as usize, bytes.0.len());
+            bytes.0[..len].to_vec()
+        }
+        None => Vec::new(),
+    };
+
+    SMCError::TypeMismatch(ConversionError {
+        key: None,
+        type_name: name,
+        data_type,
+        data,
+    })
+}
+
+fn encode_integer(
+    name: &'static str,
+    value: i128,
+    data_type: DataType,
+) -> Result<SMCBytes, SMCError> {
+    let id = data_type.id;
+    let (min, max, size) = if id == TYPE_FLAG {
+        (0, 1, 1)
+    } else if id == TYPE_U8 {
+        (0, i128::from(u8::max_value()), 1)
+    } else if id == TYPE_U16 {
+        (0, i128::from(u16::max_value()), 2)
+    } else if id == TYPE_U32 {
+        (0, i128::from(u32::max_value()), 4)
+    } else if id == TYPE_U64 {
+        (0, i128::from(u64::max_value()), 8)
+    } else if id == TYPE_I8 {
+        (i128::from(i8::min_value()), i128::from(i8::max_value()), 1)
+    } else if id == TYPE_I16 {
+        (
+            i128::from(i16::min_value()),
+            i128::from(i16::max_value()),
+            2,
+        )
+    } else if id == TYPE_I32 {
+        (
+            i128::from(i32::min_value()),
+            i128::from(i32::max_value()),
+            4,
+        )
+    } else if id == TYPE_I64 {
+        (
+            i128::from(i64::min_value()),
+            i128::from(i64::max_value()),
+            8,
+        )
+    } else if fixed_point(id).is_some() || id == TYPE_FLT || id == TYPE_IOFT {
+        return match (value as f64).to_smc(data_type) {
+            Err(SMCError::TypeMismatch(mut err)) => {
+                err.type_name = name;
+                Err(SMCError::TypeMismatch(err))
+            }
+            res => res,
+        };
+    } else {
+        return Err(type_mismatch(name, data_type, None));
+    };
+
+    if value < min || value > max {
+        return Err(SMCError::OutOfRange(data_type));
+    }
+
+    let mut res: SMCBytes = Default::default();
+    res.0[..size].copy_from_slice(&value.to_be_bytes()[16 - size..]);
+    Ok(res)
+}
+
+pub trait SMCType: Sized {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError>;
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<Self, SMCError>;
+}
+
+pub trait SMCNumber: SMCType + Copy {
+    fn is_native(known: KnownType) -> bool;
+    fn from_i128(value: i128) -> Option<Self>;
+    fn from_f64(value: f64) -> Option<Self>;
+}
+
+macro_rules! def_integer_number {
+    ( $t:ty, $($known:ident),+ ) => {
+        impl SMCNumber for $t {
+            fn is_native(known: KnownType) -> bool {
+                match known {
+                    $( KnownType::$known )|+ => true,
+                    _ => false,
+                }
+            }
+
+            fn from_i128(value: i128) -> Option<$t> {
+                if value < <$t>::min_value() as i128 || value > <$t>::max_value() as i128 {
+                    None
+                } else {
+                    Some(value as $t)
+                }
+            }
+
+            fn from_f64(value: f64) -> Option<$t> {
+                let value = value.round();
+                if value.is_finite()
+                    && value >= <$t>::min_value() as f64
+                    && value <= <$t>::max_value() as f64
+                {
+                    Some(value as $t)
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
+def_integer_number!(u8, U8);
+def_integer_number!(u16, U16);
+def_integer_number!(u32, U32);
+def_integer_number!(u64, U64);
+def_integer_number!(i8, I8);
+def_integer_number!(i16, I16);
+def_integer_number!(i32, I32);
+def_integer_number!(i64, I64);
+
+macro_rules! def_float_number {
+    ( $t:ty ) => {
+        impl SMCNumber for $t {
+            fn is_native(known: KnownType) -> bool {
+                match known {
+                    KnownType::Flt | KnownType::Ioft | KnownType::Fp(..) | KnownType::Sp(..) => {
+                        true
+                    }
+                    _ => false,
+                }
+            }
+
+            fn from_i128(value: i128) -> Option<$t> {
+                Some(value as $t)
+            }
+
+            fn from_f64(value: f64) -> Option<$t> {
+                let value = value as $t;
+                if value.is_infinite() {
+                    None
+                } else {
+                    Some(value)
+                }
+            }
+        }
+    };
+}
+
+def_float_number!(f32);
+def_float_number!(f64);
+
+pub(crate) fn decode_strict<T: SMCNumber>(
+    data_type: DataType,
+    bytes: SMCBytes,
+) -> Result<T, SMCError> {
+    match data_type.known() {
+        Some(known) if T::is_native(known) => T::from_smc(data_type, bytes),
+        _ => Err(type_mismatch(
+            std::any::type_name::<T>(),
+            data_type,
+            Some(&bytes),
+        )),
+    }
+}
+
+pub(crate) fn decode_lossy<T: SMCNumber>(
+    data_type: DataType,
+    bytes: SMCBytes,
+) -> Result<T, SMCError> {
+    let value = match data_type.known() {
+        Some(KnownType::Flag) => bool::from_smc(data_type, bytes).map(|v| T::from_i128(v as i128)),
+        Some(KnownType::U8) | Some(KnownType::U16) | Some(KnownType::U32)
+        | Some(KnownType::U64) => u64::from_smc(data_type, bytes).map(|v| T::from_i128(v as i128)),
+        Some(KnownType::I8) | Some(KnownType::I16) | Some(KnownType::I32)
+        | Some(KnownType::I64) => i64::from_smc(data_type, bytes).map(|v| T::from_i128(v as i128)),
+        _ => f64::from_smc(data_type, bytes).map(T::from_f64),
+    };
+
+    match value {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => Err(SMCError::OutOfRange(data_type)),
+        Err(SMCError::TypeMismatch(mut err)) => {
+            err.type_name = std::any::type_name::<T>();
+            Err(SMCError::TypeMismatch(err))
+        }
+        Err(err) => Err(err),
+    }
 }
 
 impl SMCType for bool {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
         if data_type.id == TYPE_FLAG {
             let mut res: SMCBytes = Default::default();
             res.0[0] = u8::from(*self);
-            res
+            Ok(res)
         } else {
-            panic!("Cannot convert bool to {:?}", data_type);
+            encode_integer("bool", i128::from(u8::from(*self)), data_type)
         }
     }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> bool {
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<bool, SMCError> {
         if data_type.id == TYPE_FLAG {
-            bytes.0[0] != 0
+            Ok(bytes.0[0] != 0)
         } else {
-            panic!("Cannot convert {:?} to bool", data_type);
+            Err(type_mismatch("bool", data_type, Some(&bytes)))
         }
     }
 }
 
 impl SMCType for i8 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
-        if data_type.id == TYPE_I8 {
-            let mut res: SMCBytes = Default::default();
-            unsafe {
-                memcpy(
-                    &mut res as *mut _ as *mut c_void,
-                    self as *const _ as *const c_void,
-                    std::mem::size_of::<i8>(),
-                );
-            }
-            res
-        } else {
-            panic!("Cannot convert i8 to {:?}", data_type);
-        }
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("i8", i128::from(*self), data_type)
     }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> i8 {
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<i8, SMCError> {
         if data_type.id == TYPE_I8 {
-            unsafe { *(&(bytes.0[0]) as *const _ as *const i8) }
+            Ok(unsafe { std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const i8) })
         } else {
-            panic!("Cannot convert {:?} to i8", data_type);
+            Err(type_mismatch("i8", data_type, Some(&bytes)))
         }
     }
 }
 
 impl SMCType for u8 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("u8", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<u8, SMCError> {
         if data_type.id == TYPE_U8 {
-            let mut res: SMCBytes = Default::default();
-            res.0[0] = *self;
-            res
+            Ok(bytes.0[0])
+        } else {
+            Err(type_mismatch("u8", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for i16 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("i16", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<i16, SMCError> {
+        if data_type.id == TYPE_I16 {
+            Ok(i16::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const i16)
+            }))
         } else {
-            panic!("Cannot convert u8 to {:?}", data_type);
+            Err(type_mismatch("i16", data_type, Some(&bytes)))
         }
     }
+}
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> u8 {
+impl SMCType for u16 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("u16", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<u16, SMCError> {
         if data_type.id == TYPE_U8 {
-            bytes.0[0]
+            Ok(u16::from(<u8 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_U16 {
+            Ok(u16::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u16)
+            }))
         } else {
-            panic!("Cannot convert {:?} to u8", data_type);
+            Err(type_mismatch("u16", data_type, Some(&bytes)))
         }
     }
 }
 
-impl SMCType for i16 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
-        if data_type.id == TYPE_I16 {
-            let mut res: SMCBytes = Default::default();
-            unsafe {
-                memcpy(
-                    &mut res as *mut _ as *mut c_void,
-                    &self.to_be() as *const _ as *const c_void,
-                    std::mem::size_of::<i16>(),
-                );
-            }
-            res
+impl SMCType for i32 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("i32", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<i32, SMCError> {
+        if data_type.id == TYPE_I32 {
+            Ok(i32::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const i32)
+            }))
         } else {
-            panic!("Cannot convert i16 to {:?}", data_type);
+            Err(type_mismatch("i32", data_type, Some(&bytes)))
         }
     }
+}
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> i16 {
-        if data_type.id == TYPE_I16 {
-            i16::from_be(unsafe { *(&(bytes.0[0]) as *const _ as *const i16) })
+impl SMCType for u32 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("u32", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<u32, SMCError> {
+        if data_type.id == TYPE_U8 {
+            Ok(u32::from(<u8 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_U16 {
+            Ok(u32::from(<u16 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_U32 {
+            Ok(u32::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u32)
+            }))
         } else {
-            panic!("Cannot convert {:?} to i16", data_type);
+            Err(type_mismatch("u32", data_type, Some(&bytes)))
         }
     }
 }
 
-impl SMCType for u16 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
-        if data_type.id == TYPE_U16 {
-            let mut res: SMCBytes = Default::default();
-            unsafe {
-                memcpy(
-                    &mut res as *mut _ as *mut c_void,
-                    &self.to_be() as *const _ as *const c_void,
-                    std::mem::size_of::<u16>(),
-                );
-            }
-            res
+impl SMCType for i64 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("i64", i128::from(*self), data_type)
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<i64, SMCError> {
+        if data_type.id == TYPE_I8 {
+            Ok(i64::from(<i8 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_I16 {
+            Ok(i64::from(<i16 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_I32 {
+            Ok(i64::from(<i32 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_I64 {
+            Ok(i64::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const i64)
+            }))
         } else {
-            panic!("Cannot convert u16 to {:?}", data_type);
+            Err(type_mismatch("i64", data_type, Some(&bytes)))
         }
     }
+}
+
+impl SMCType for u64 {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        encode_integer("u64", i128::from(*self), data_type)
+    }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> u16 {
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<u64, SMCError> {
         if data_type.id == TYPE_U8 {
-            u16::from(<u8 as SMCType>::from_smc(data_type, bytes))
+            Ok(u64::from(<u8 as SMCType>::from_smc(data_type, bytes)?))
         } else if data_type.id == TYPE_U16 {
-            u16::from_be(unsafe { *(&(bytes.0[0]) as *const _ as *const u16) })
+            Ok(u64::from(<u16 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_U32 {
+            Ok(u64::from(<u32 as SMCType>::from_smc(data_type, bytes)?))
+        } else if data_type.id == TYPE_U64 {
+            Ok(u64::from_be(unsafe {
+                std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u64)
+            }))
         } else {
-            panic!("Cannot convert {:?} to u16", data_type);
+            Err(type_mismatch("u64", data_type, Some(&bytes)))
         }
     }
 }
 
-impl SMCType for i32 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
-        if data_type.id == TYPE_I32 {
+impl SMCType for SMCString {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        if data_type.id == TYPE_CH8 {
             let mut res: SMCBytes = Default::default();
-            unsafe {
-                memcpy(
-                    &mut res as *mut _ as *mut c_void,
-                    &self.to_be() as *const _ as *const c_void,
-                    std::mem::size_of::<i32>(),
-                );
-            }
-            res
+            let max = std::cmp::min(data_type.size as usize, res.0.len());
+            write_string(&mut res.0[0] as *mut u8, &self.0, max);
+            Ok(res)
         } else {
-            panic!("Cannot convert i32 to {:?}", data_type);
+            Err(type_mismatch("SMCString", data_type, None))
         }
     }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> i32 {
-        if data_type.id == TYPE_I32 {
-            i32::from_be(unsafe { *(&(bytes.0[0]) as *const _ as *const i32) })
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<SMCString, SMCError> {
+        if data_type.id == TYPE_CH8 {
+            let max = std::cmp::min(data_type.size as usize, bytes.0.len());
+            Ok(SMCString(read_string(&bytes.0[0] as *const u8, max)))
         } else {
-            panic!("Cannot convert {:?} to i32", data_type);
+            Err(type_mismatch("SMCString", data_type, Some(&bytes)))
         }
     }
 }
 
-impl SMCType for u32 {
-    fn to_smc(&self, data_type: DataType) -> SMCBytes {
-        if data_type.id == TYPE_U32 {
+impl SMCType for String {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        SMCString::to_smc(&SMCString::from(self.as_str()), data_type).map_err(|e| match e {
+            SMCError::TypeMismatch(mut err) => {
+                err.type_name = "String";
+                SMCError::TypeMismatch(err)
+            }
+            err => err,
+        })
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<String, SMCError> {
+        match SMCString::from_smc(data_type, bytes) {
+            Ok(s) => Ok(s.0),
+            Err(SMCError::TypeMismatch(mut err)) => {
+                err.type_name = "String";
+                Err(SMCError::TypeMismatch(err))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl SMCType for HexBytes {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        if data_type.id == TYPE_HEX {
             let mut res: SMCBytes = Default::default();
-            unsafe {
-                memcpy(
-                    &mut res as *mut _ as *mut c_void,
-                    &self.to_be() as *const _ as *const c_void,
-                    std::mem::size_of::<u32>(),
-                );
+            if self.0.len() != data_type.size as usize || self.0.len() > res.0.len() {
+                return Err(SMCError::OutOfRange(data_type));
             }
-            res
+            res.0[..self.0.len()].copy_from_slice(&self.0);
+            Ok(res)
         } else {
-            panic!("Cannot convert u32 to {:?}", data_type);
+            Err(type_mismatch("HexBytes", data_type, None))
         }
     }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> u32 {
-        if data_type.id == TYPE_U8 {
-            u32::from(<u8 as SMCType>::from_smc(data_type, bytes))
-        } else if data_type.id == TYPE_U16 {
-            u32::from(<u16 as SMCType>::from_smc(data_type, bytes))
-        } else if data_type.id == TYPE_U32 {
-            u32::from_be(unsafe { *(&(bytes.0[0]) as *const _ as *const u32) })
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<HexBytes, SMCError> {
+        if data_type.id == TYPE_HEX {
+            let len = std::cmp::min(data_type.size as usize, bytes.0.len());
+            Ok(HexBytes(bytes.0[..len].to_vec()))
+        } else {
+            Err(type_mismatch("HexBytes", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for FanDescriptor {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("FanDescriptor", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<FanDescriptor, SMCError> {
+        if data_type.id == TYPE_FAN && data_type.size >= 4 {
+            let max = std::cmp::min(data_type.size as usize, bytes.0.len());
+            let name = read_string(unsafe { (&bytes.0[0] as *const u8).add(4) }, max - 4);
+            Ok(FanDescriptor {
+                fan_type: FanType::from(bytes.0[0]),
+                zone: bytes.0[1],
+                location: FanLocation::from(bytes.0[2]),
+                rotation: bytes.0[3],
+                name,
+            })
+        } else {
+            Err(type_mismatch("FanDescriptor", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for KeyboardBacklight {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        if data_type.id == TYPE_LKB || data_type.id == TYPE_LKS {
+            let mut res: SMCBytes = Default::default();
+            res.0[0] = self.brightness;
+            res.0[1] = self.flags;
+            Ok(res)
+        } else {
+            Err(type_mismatch("KeyboardBacklight", data_type, None))
+        }
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<KeyboardBacklight, SMCError> {
+        if data_type.id == TYPE_LKB || data_type.id == TYPE_LKS {
+            Ok(KeyboardBacklight {
+                brightness: bytes.0[0],
+                flags: bytes.0[1],
+            })
         } else {
-            panic!("Cannot convert {:?} to u32", data_type);
+            Err(type_mismatch("KeyboardBacklight", data_type, Some(&bytes)))
         }
     }
 }
 
-impl SMCType for RawFan {
-    fn to_smc(&self, _data_type: DataType) -> SMCBytes {
-        panic!("You can't write a RawFan type");
+impl SMCType for Revision {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("Revision", data_type, None))
     }
 
-    fn from_smc(data_type: DataType, bytes: SMCBytes) -> RawFan {
-        if data_type.id == TYPE_FAN {
-            let name = read_string(
-                unsafe { (&bytes.0[0] as *const u8).add(4) },
-                (data_type.size - 4) as usize,
-            );
-            RawFan { name }
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<Revision, SMCError> {
+        if data_type.id == TYPE_REV && data_type.size >= 6 {
+            Ok(Revision {
+                major: bytes.0[0],
+                minor: bytes.0[1],
+                build: bytes.0[2],
+                reserved: bytes.0[3],
+                release: u16::from_be_bytes([bytes.0[4], bytes.0[5]]),
+            })
         } else {
-            panic!("Cannot convert {:?} to RawFan", data_type);
+            Err(type_mismatch("Revision", data_type, Some(&bytes)))
         }
     }
 }
 
+impl SMCType for DriveBayInfo {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("DriveBayInfo", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<DriveBayInfo, SMCError> {
+        if data_type.id == TYPE_HDI && data_type.size >= 6 {
+            let sensor = u32::from_be_bytes([bytes.0[2], bytes.0[3], bytes.0[4], bytes.0[5]]);
+            Ok(DriveBayInfo {
+                bay: bytes.0[0],
+                present: bytes.0[1] & 0x01 != 0,
+                sensor: if sensor == 0 {
+                    None
+                } else {
+                    Some(FourCharCode(sensor))
+                },
+            })
+        } else {
+            Err(type_mismatch("DriveBayInfo", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for ThermalLimits {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("ThermalLimits", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<ThermalLimits, SMCError> {
+        if data_type.id == TYPE_LIM && data_type.size >= 3 {
+            Ok(ThermalLimits {
+                cpu: bytes.0[0],
+                gpu: bytes.0[1],
+                memory: bytes.0[2],
+            })
+        } else {
+            Err(type_mismatch("ThermalLimits", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for MotionSensorStatus {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("MotionSensorStatus", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<MotionSensorStatus, SMCError> {
+        if data_type.id == TYPE_MSS && data_type.size >= 2 {
+            Ok(MotionSensorStatus {
+                enabled: bytes.0[0] & 0x01 != 0,
+                triggered: bytes.0[1] != 0,
+            })
+        } else {
+            Err(type_mismatch("MotionSensorStatus", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for AmbientLightInfo {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("AmbientLightInfo", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<AmbientLightInfo, SMCError> {
+        if data_type.id == TYPE_ALI && data_type.size >= 2 {
+            Ok(AmbientLightInfo {
+                sensor_type: bytes.0[0],
+                cpu_affected: bytes.0[1] != 0,
+            })
+        } else {
+            Err(type_mismatch("AmbientLightInfo", data_type, Some(&bytes)))
+        }
+    }
+}
+
+impl SMCType for AmbientLightParams {
+    fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
+        Err(type_mismatch("AmbientLightParams", data_type, None))
+    }
+
+    fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<AmbientLightParams, SMCError> {
+        if data_type.id == TYPE_ALP && data_type.size >= 4 {
+            Ok(AmbientLightParams {
+                gain: u16::from_be_bytes([bytes.0[0], bytes.0[1]]),
+                offset: u16::from_be_bytes([bytes.0[2], bytes.0[3]]),
+            })
+        } else {
+            Err(type_mismatch("AmbientLightParams", data_type, Some(&bytes)))
+        }
+    }
+}
+
+// Scales `value` into a big-endian 16-bit fixed-point word, refusing anything
+// the word can't hold instead of letting the cast saturate.
+fn encode_fixed(
+    value: f64,
+    frac: u32,
+    signed: bool,
+    data_type: DataType,
+) -> Result<SMCBytes, SMCError> {
+    let scaled = (value * f64::from(1_u32 << frac)).trunc();
+    let (min, max) = if signed {
+        (f64::from(i16::min_value()), f64::from(i16::max_value()))
+    } else {
+        (0.0, f64::from(u16::max_value()))
+    };
+    if !scaled.is_finite() || scaled < min || scaled > max {
+        return Err(SMCError::OutOfRange(data_type));
+    }
+
+    let word = if signed {
+        scaled as i16 as u16
+    } else {
+        scaled as u16
+    };
+    let mut res: SMCBytes = Default::default();
+    res.0[..2].copy_from_slice(&word.to_be_bytes());
+    Ok(res)
+}
+
 macro_rules! def_float {
     ( $t:ty ) => {
         impl SMCType for $t {
-            fn to_smc(&self, data_type: DataType) -> SMCBytes {
+            fn to_smc(&self, data_type: DataType) -> Result<SMCBytes, SMCError> {
                 if data_type.id == TYPE_FPE2 {
-                    if self.is_sign_negative() {
-                        panic!(concat!(
-                            "Cannot convert negative ",
-                            stringify!($t),
-                            " to fpe2"
-                        ));
-                    }
-
-                    let value = ((self * 4.0) as u16).to_be();
-
-                    let mut res: SMCBytes = Default::default();
-                    unsafe {
-                        memcpy(
-                            &mut res as *mut _ as *mut c_void,
-                            &value as *const _ as *const c_void,
-                            std::mem::size_of::<u16>(),
-                        );
-                    }
-                    res
+                    encode_fixed(f64::from(*self), 2, false, data_type)
                 } else if data_type.id == TYPE_SP78 {
-                    let value = ((self * 256.0) as i16).to_be();
-
+                    encode_fixed(f64::from(*self), 8, true, data_type)
+                } else if let Some((signed, frac)) = fixed_point(data_type.id) {
+                    encode_fixed(f64::from(*self), frac, signed, data_type)
+                } else if data_type.id == TYPE_FLT {
+                    // flt is little-endian on both Intel and Apple Silicon
                     let mut res: SMCBytes = Default::default();
-                    unsafe {
-                        memcpy(
-                            &mut res as *mut _ as *mut c_void,
-                            &value as *const _ as *const c_void,
-                            std::mem::size_of::<u16>(),
-                        );
+                    res.0[..4].copy_from_slice(&(*self as f32).to_bits().to_le_bytes());
+                    Ok(res)
+                } else if data_type.id == TYPE_IOFT {
+                    let scaled = (f64::from(*self) * 65536.0).trunc();
+                    // u64::MAX isn't exactly representable, so bound by 2^64 exclusive
+                    if !(0.0..18_446_744_073_709_551_616.0).contains(&scaled) {
+                        return Err(SMCError::OutOfRange(data_type));
                     }
-                    res
-                } else if data_type.id == TYPE_FLT {
-                    let value = (*self as f32).to_bits();
 
                     let mut res: SMCBytes = Default::default();
-                    unsafe {
-                        memcpy(
-                            &mut res as *mut _ as *mut c_void,
-                            &value as *const _ as *const c_void,
-                            std::mem::size_of::<u32>(),
-                        );
-                    }
-                    res
+                    res.0[..8].copy_from_slice(&(scaled as u64).to_le_bytes());
+                    Ok(res)
+                } else if self.is_finite() {
+                    encode_integer(stringify!($t), self.round() as i128, data_type)
                 } else {
-                    panic!(
-                        concat!("Cannot convert ", stringify!($t), " to {:?}"),
-                        data_type
-                    );
+                    Err(SMCError::OutOfRange(data_type))
                 }
             }
 
-            fn from_smc(data_type: DataType, bytes: SMCBytes) -> $t {
+            fn from_smc(data_type: DataType, bytes: SMCBytes) -> Result<$t, SMCError> {
                 if data_type.id == TYPE_FPE2 {
-                    (u16::from_be(unsafe { *(&bytes.0[0] as *const _ as *const u16) }) as $t) / 4.0
+                    Ok((u16::from_be(unsafe {
+                        std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u16)
+                    }) as $t)
+                        / 4.0)
                 } else if data_type.id == TYPE_SP78 {
-                    (i16::from_be(unsafe { *(&bytes.0[0] as *const _ as *const i16) }) as $t)
-                        / 256.0
+                    Ok((i16::from_be(unsafe {
+                        std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const i16)
+                    }) as $t)
+                        / 256.0)
+                } else if let Some((signed, frac)) = fixed_point(data_type.id) {
+                    let raw = u16::from_be(unsafe {
+                        std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u16)
+                    });
+                    let value = if signed {
+                        (raw as i16) as $t
+                    } else {
+                        raw as $t
+                    };
+                    Ok(value / ((1_u32 << frac) as $t))
                 } else if data_type.id == TYPE_FLT {
-                    f32::from_bits(unsafe { *(&bytes.0[0] as *const _ as *const u32) }) as $t
+                    Ok(f32::from_bits(u32::from_le_bytes([
+                        bytes.0[0], bytes.0[1], bytes.0[2], bytes.0[3],
+                    ])) as $t)
+                } else if data_type.id == TYPE_IOFT {
+                    Ok((u64::from_le(unsafe {
+                        std::ptr::read_unaligned(&bytes.0[0] as *const _ as *const u64)
+                    }) as f64
+                        / 65536.0) as $t)
                 } else {
-                    panic!(
-                        concat!("Cannot convert {:?} to ", stringify!($t)),
-                        data_type
-                    );
+                    Err(type_mismatch(stringify!($t), data_type, Some(&bytes)))
                 }
             }
         }
@@ -321,3 +1052,108 @@ macro_rules! def_float {
 
 def_float!(f32);
 def_float!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLT: DataType = DataType {
+        id: TYPE_FLT,
+        size: 4,
+    };
+
+    fn bytes(data: &[u8]) -> SMCBytes {
+        let mut res = SMCBytes::default();
+        res.0[..data.len()].copy_from_slice(data);
+        res
+    }
+
+    #[test]
+    fn flt_recorded_values() {
+        // typical F0Ac, F0Mn and F0Mx readings on Apple Silicon
+        let samples: &[(&[u8], f32)] = &[
+            (&[0x00, 0xc0, 0x10, 0x45], 2316.0),
+            (&[0x00, 0x00, 0x96, 0x44], 1200.0),
+            (&[0x00, 0x20, 0x99, 0x45], 4900.0),
+        ];
+
+        for (data, value) in samples {
+            assert_eq!(f32::from_smc(FLT, bytes(data)).unwrap(), *value);
+            assert_eq!(&value.to_smc(FLT).unwrap().0[..4], *data);
+        }
+    }
+
+    #[test]
+    fn flt_round_trip() {
+        for value in &[0.0_f64, -1.5, 42.25, 1234.5, 65535.0] {
+            let encoded = value.to_smc(FLT).unwrap();
+            assert_eq!(f64::from_smc(FLT, encoded).unwrap(), *value);
+        }
+    }
+
+    fn out_of_range<T: SMCType>(value: T, data_type: DataType) -> bool {
+        match value.to_smc(data_type) {
+            Err(SMCError::OutOfRange(ty)) => ty == data_type,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn fixed_point_range() {
+        let fp88 = DataType {
+            id: four_char_code!("fp88"),
+            size: 2,
+        };
+        let sp4b = DataType {
+            id: four_char_code!("sp4b"),
+            size: 2,
+        };
+
+        assert_eq!(&255.5_f32.to_smc(fp88).unwrap().0[..2], &[0xff, 0x80]);
+        assert!(out_of_range(256.0_f32, fp88));
+        assert!(out_of_range(-1.0_f64, fp88));
+        assert_eq!(&(-16.0_f64).to_smc(sp4b).unwrap().0[..2], &[0x80, 0x00]);
+        assert!(out_of_range(16.0_f64, sp4b));
+        assert!(out_of_range(std::f32::NAN, sp4b));
+    }
+
+    #[test]
+    fn fpe2_range() {
+        let fpe2 = DataType {
+            id: TYPE_FPE2,
+            size: 2,
+        };
+
+        assert_eq!(&1200.0_f64.to_smc(fpe2).unwrap().0[..2], &[0x12, 0xc0]);
+        assert_eq!(&16383.75_f32.to_smc(fpe2).unwrap().0[..2], &[0xff, 0xff]);
+        assert!(out_of_range(20000.0_f64, fpe2));
+        assert!(out_of_range(-1.0_f32, fpe2));
+        assert!(out_of_range(std::f64::NAN, fpe2));
+        assert!(out_of_range(std::f64::INFINITY, fpe2));
+    }
+
+    #[test]
+    fn sp78_range() {
+        let sp78 = DataType {
+            id: TYPE_SP78,
+            size: 2,
+        };
+
+        assert_eq!(&61.5_f32.to_smc(sp78).unwrap().0[..2], &[0x3d, 0x80]);
+        assert_eq!(&(-128.0_f64).to_smc(sp78).unwrap().0[..2], &[0x80, 0x00]);
+        assert!(out_of_range(200.0_f64, sp78));
+        assert!(out_of_range(-129.0_f32, sp78));
+        assert!(out_of_range(std::f32::NAN, sp78));
+    }
+
+    #[test]
+    fn signedness() {
+        let signed = |id| KnownType::from_id(id).unwrap().is_signed();
+
+        assert!(signed(TYPE_SP78));
+        assert!(signed(TYPE_FLT));
+        assert!(!signed(TYPE_IOFT));
+        assert!(!signed(TYPE_FPE2));
+        assert!(!signed(TYPE_U16));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{Fan, FanMode, SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanCurve {
+    points: Vec<(f64, f64)>,
+    hysteresis: f64,
+}
+
+impl FanCurve {
+    pub fn new<I>(points: I) -> FanCurve
+    where
+        I: IntoIterator<Item = (f64, f64)>,
+    {
+        let mut points: Vec<(f64, f64)> = points
+            .into_iter()
+            .filter(|(temp, percent)| !temp.is_nan() && !percent.is_nan())
+            .map(|(temp, percent)| (temp, percent.max(0.0).min(100.0)))
+            .collect();
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        FanCurve {
+            points,
+            hysteresis: 0.0,
+        }
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: f64) -> FanCurve {
+        self.hysteresis = hysteresis.max(0.0);
+        self
+    }
+
+    #[inline]
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    #[inline]
+    pub fn hysteresis(&self) -> f64 {
+        self.hysteresis
+    }
+
+    pub fn percent(&self, temp: f64) -> f64 {
+        let first = match self.points.first() {
+            Some(first) => *first,
+            None => return 100.0,
+        };
+        if temp <= first.0 {
+            return first.1;
+        }
+
+        for window in self.points.windows(2) {
+            let (t0, p0) = window[0];
+            let (t1, p1) = window[1];
+            if temp <= t1 {
+                if t1 <= t0 {
+                    return p1;
+                }
+                return p0 + (p1 - p0) * (temp - t0) / (t1 - t0);
+            }
+        }
+
+        self.points[self.points.len() - 1].1
+    }
+}
+
+#[derive(Debug)]
+pub struct FanCurveController {
+    fan: Fan,
+    sensor: FourCharCode,
+    curve: FanCurve,
+    last_temp: Option<f64>,
+    last_percent: Option<f64>,
+}
+
+impl FanCurveController {
+    pub fn new(fan: Fan, sensor: FourCharCode, curve: FanCurve) -> FanCurveController {
+        FanCurveController {
+            fan,
+            sensor,
+            curve,
+            last_temp: None,
+            last_percent: None,
+        }
+    }
+
+    #[inline]
+    pub fn fan(&self) -> &Fan {
+        &self.fan
+    }
+
+    #[inline]
+    pub fn sensor(&self) -> FourCharCode {
+        self.sensor
+    }
+
+    #[inline]
+    pub fn curve(&self) -> &FanCurve {
+        &self.curve
+    }
+
+    pub fn set_curve(&mut self, curve: FanCurve) {
+        self.curve = curve;
+        self.last_temp = None;
+        self.last_percent = None;
+    }
+
+    pub fn tick(&mut self) -> Result<f64, SMCError> {
+        let smc = SMC(self.fan.smc_repr.clone());
+        let temp = f64::from(smc.temperature(self.sensor)?);
+
+        let temp = match self.last_temp {
+            Some(last) if temp < last && last - temp < self.curve.hysteresis => last,
+            _ => temp,
+        };
+        self.last_temp = Some(temp);
+
+        let percent = self.curve.percent(temp);
+        if self.last_percent == Some(percent) {
+            return Ok(percent);
+        }
+
+        if percent <= 0.0 {
+            self.fan.set_mode(FanMode::Auto)?;
+        } else {
+            self.fan.set_percent(percent)?;
+        }
+        self.last_percent = Some(percent);
+
+        Ok(percent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MemoryBackend, Platform, WritePolicy};
+
+    use four_char_code::four_char_code;
+
+    const FLT: DataType = DataType {
+        id: four_char_code!("flt "),
+        size: 4,
+    };
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+    const SENSOR: FourCharCode = four_char_code!("TC0P");
+
+    #[test]
+    fn percent_interpolates() {
+        let curve = FanCurve::new(vec![(80.0, 100.0), (40.0, 0.0), (60.0, 50.0)]);
+
+        assert_eq!(curve.points(), &[(40.0, 0.0), (60.0, 50.0), (80.0, 100.0)]);
+        assert_eq!(curve.percent(20.0), 0.0);
+        assert_eq!(curve.percent(40.0), 0.0);
+        assert_eq!(curve.percent(50.0), 25.0);
+        assert_eq!(curve.percent(70.0), 75.0);
+        assert_eq!(curve.percent(95.0), 100.0);
+        assert_eq!(FanCurve::new(vec![]).percent(50.0), 100.0);
+    }
+
+    #[test]
+    fn percent_clamps_and_skips_nan() {
+        let curve = FanCurve::new(vec![(40.0, -10.0), (std::f64::NAN, 50.0), (60.0, 150.0)]);
+
+        assert_eq!(curve.points(), &[(40.0, 0.0), (60.0, 100.0)]);
+        assert_eq!(curve.percent(50.0), 50.0);
+    }
+
+    #[test]
+    fn tick_applies_hysteresis() {
+        let backend = MemoryBackend::with_platform(Platform::AppleSilicon);
+        backend.insert(four_char_code!("FNum"), UI8, 1_u8).unwrap();
+        backend.insert(four_char_code!("F0Ac"), FLT, 0.0).unwrap();
+        backend
+            .insert(four_char_code!("F0Mn"), FLT, 1000.0)
+            .unwrap();
+        backend
+            .insert(four_char_code!("F0Mx"), FLT, 5000.0)
+            .unwrap();
+        backend.insert(four_char_code!("F0Tg"), FLT, 0.0).unwrap();
+        backend.insert(four_char_code!("F0Md"), UI8, 0_u8).unwrap();
+        backend.insert(four_char_code!("Ftst"), UI8, 0_u8).unwrap();
+        backend.insert(SENSOR, FLT, 60.0).unwrap();
+        let smc = SMC::with_backend(backend);
+        smc.set_write_policy(WritePolicy::allow_all());
+
+        let curve = FanCurve::new(vec![(40.0, 0.0), (80.0, 100.0)]).with_hysteresis(5.0);
+        let mut controller = FanCurveController::new(smc.fan(0).unwrap(), SENSOR, curve);
+        let set_temp = |temp: f32| smc.write_key(SENSOR, temp).unwrap();
+
+        assert_eq!(controller.tick().unwrap(), 50.0);
+        assert_eq!(
+            smc.read_key::<f32>(four_char_code!("F0Tg")).unwrap(),
+            3000.0
+        );
+
+        set_temp(57.0);
+        assert_eq!(controller.tick().unwrap(), 50.0);
+
+        set_temp(54.0);
+        assert_eq!(controller.tick().unwrap(), 35.0);
+
+        set_temp(64.0);
+        assert_eq!(controller.tick().unwrap(), 60.0);
+        assert_eq!(
+            smc.read_key::<f32>(four_char_code!("F0Tg")).unwrap(),
+            3400.0
+        );
+
+        set_temp(30.0);
+        assert_eq!(controller.tick().unwrap(), 0.0);
+        assert_eq!(smc.fan(0).unwrap().mode().unwrap(), FanMode::Auto);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    keys::consts::{ADAPTER_WATTS, BATTERY_CHARGE, BATTERY_INFO},
+    power::{
+        APPLE_CPU_POWER_KEYS, APPLE_GPU_POWER_KEYS, BATTERY_INFO_AC, BATTERY_INFO_CHARGING,
+        INTEL_CPU_POWER_KEYS, INTEL_GPU_POWER_KEYS, SYSTEM_POWER_KEYS,
+    },
+    Celsius, FanInfo, FanMode, SMCError, SMCVal, TemperatureSensor, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+const TOP_TEMPERATURES: usize = 5;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dashboard {
+    pub fans: Vec<FanInfo>,
+    pub temperatures: Vec<(TemperatureSensor, Celsius)>,
+    pub cpu_power: Option<f64>,
+    pub gpu_power: Option<f64>,
+    pub system_power: Option<f64>,
+    pub battery_charge: Option<u8>,
+    pub charging: Option<bool>,
+    pub ac_connected: Option<bool>,
+}
+
+impl Dashboard {
+    pub fn hottest(&self) -> Option<&(TemperatureSensor, Celsius)> {
+        self.temperatures.first()
+    }
+}
+
+// The sensors and fans are discovered once per handle: walking the key index
+// costs a driver call per key, far more than the values themselves.
+pub(crate) struct DashboardLayout {
+    sensors: Vec<TemperatureSensor>,
+    fans: Vec<(u32, String)>,
+    cpu_keys: Vec<FourCharCode>,
+    gpu_keys: Vec<FourCharCode>,
+    keys: Vec<FourCharCode>,
+}
+
+struct Values(HashMap<FourCharCode, Result<SMCVal, SMCError>>);
+
+impl Values {
+    fn take(&mut self, key: FourCharCode) -> Result<Option<SMCVal>, SMCError> {
+        match self.0.remove(&key) {
+            Some(Ok(val)) => Ok(Some(val)),
+            Some(Err(ref err)) if err.is_not_found() => Ok(None),
+            None => Ok(None),
+            Some(Err(err)) => Err(err),
+        }
+    }
+
+    fn first_number(&mut self, keys: &[FourCharCode]) -> Result<Option<f64>, SMCError> {
+        for key in keys {
+            if let Some(val) = self.take(*key)? {
+                return Ok(val.as_f64());
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl SMC {
+    fn existing_keys(&self, keys: &[FourCharCode]) -> Result<Vec<FourCharCode>, SMCError> {
+        let mut res = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.0.key_information(*key) {
+                Ok(_) => res.push(*key),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(res)
+    }
+
+    fn dashboard_layout(&self) -> Result<Arc<DashboardLayout>, SMCError> {
+        if let Some(ref layout) = *self.0.dashboard_layout.lock().unwrap() {
+            return Ok(layout.clone());
+        }
+
+        let sensors: Vec<TemperatureSensor> = self.temperature_sensors()?.collect();
+        let cpu_keys =
+            self.existing_keys(&self.platform_keys(INTEL_CPU_POWER_KEYS, APPLE_CPU_POWER_KEYS))?;
+        let gpu_keys =
+            self.existing_keys(&self.platform_keys(INTEL_GPU_POWER_KEYS, APPLE_GPU_POWER_KEYS))?;
+        let fans = match self.fans_lossy() {
+            Ok(fans) => fans
+                .iter()
+                .map(|fan| (fan.id(), fan.name().to_string()))
+                .collect(),
+            Err(ref err) if err.is_not_found() => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut keys: Vec<FourCharCode> = sensors.iter().map(|s| s.key()).collect();
+        keys.extend_from_slice(&cpu_keys);
+        keys.extend_from_slice(&gpu_keys);
+        keys.extend(self.existing_keys(SYSTEM_POWER_KEYS)?);
+        keys.extend(self.existing_keys(&[BATTERY_CHARGE, BATTERY_INFO, ADAPTER_WATTS])?);
+
+        let layout = Arc::new(DashboardLayout {
+            sensors,
+            fans,
+            cpu_keys,
+            gpu_keys,
+            keys,
+        });
+        *self.0.dashboard_layout.lock().unwrap() = Some(layout.clone());
+        Ok(layout)
+    }
+
+    /// The first call discovers the temperature sensors and fans; later calls
+    /// only batch-read their values. `clear_key_info_cache` forgets the
+    /// discovered set.
+    pub fn dashboard(&self) -> Result<Dashboard, SMCError> {
+        let layout = self.dashboard_layout()?;
+        let keys = layout.keys.clone();
+
+        let results = self.read_many(&keys);
+        let mut values = Values(keys.into_iter().zip(results).collect());
+
+        let mut temperatures = Vec::with_capacity(layout.sensors.len());
+        for sensor in layout.sensors.iter() {
+            if let Some(temp) = values.take(sensor.key())?.and_then(|val| val.as_f64()) {
+                temperatures.push((sensor.clone(), Celsius(temp as f32)));
+            }
+        }
+        temperatures.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        temperatures.truncate(TOP_TEMPERATURES);
+
+        let cpu_power = values.first_number(&layout.cpu_keys)?;
+        let gpu_power = values.first_number(&layout.gpu_keys)?;
+        let system_power = values.first_number(SYSTEM_POWER_KEYS)?;
+        let battery_charge = values
+            .take(BATTERY_CHARGE)?
+            .and_then(|val| val.data().first().cloned());
+        let battery_info = values
+            .take(BATTERY_INFO)?
+            .and_then(|val| val.data().first().cloned());
+        let adapter_watts = values.first_number(&[ADAPTER_WATTS])?;
+
+        let (charging, ac_connected) = match battery_info {
+            Some(info) => (
+                Some(info & BATTERY_INFO_CHARGING != 0),
+                Some(info & BATTERY_INFO_AC != 0),
+            ),
+            None => (None, adapter_watts.map(|watts| watts > 0.0)),
+        };
+
+        let mut fans: Vec<FanInfo> = layout
+            .fans
+            .iter()
+            .map(|(id, name)| FanInfo {
+                id: *id,
+                name: name.clone(),
+                mode: FanMode::Auto,
+                current_speed: 0.0,
+                min_speed: 0.0,
+                max_speed: 0.0,
+                target_speed: None,
+            })
+            .collect();
+        self.refresh_fan_infos(&mut fans)?;
+
+        Ok(Dashboard {
+            fans,
+            temperatures,
+            cpu_power,
+            gpu_power,
+            system_power,
+            battery_charge,
+            charging,
+            ac_connected,
+        })
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use std::fmt;
+
+use crate::conversions::{
+    TYPE_ALI, TYPE_ALP, TYPE_FAN, TYPE_HDI, TYPE_LIM, TYPE_LKB, TYPE_LKS, TYPE_MSS, TYPE_REV,
+};
+use crate::{
+    AmbientLightInfo, AmbientLightParams, DataType, DriveBayInfo, FanDescriptor, KeyboardBacklight,
+    KnownType, MotionSensorStatus, Revision, SMCBytes, SMCError, SMCString, SMCType, SMCVal,
+    ThermalLimits, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Struct(Vec<(String, DecodedValue)>),
+}
+
+macro_rules! decoded_from {
+    ( $variant:ident, $target:ty, $( $t:ty ),* ) => {
+        $(
+            impl From<$t> for DecodedValue {
+                fn from(value: $t) -> DecodedValue {
+                    DecodedValue::$variant(<$target>::from(value))
+                }
+            }
+        )*
+    };
+}
+
+decoded_from!(Bool, bool, bool);
+decoded_from!(UInt, u64, u8, u16, u32, u64);
+decoded_from!(Int, i64, i8, i16, i32, i64);
+decoded_from!(Float, f64, f32, f64);
+decoded_from!(Str, String, String, &str);
+
+impl fmt::Display for DecodedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodedValue::Bool(v) => fmt::Display::fmt(v, f),
+            DecodedValue::Int(v) => fmt::Display::fmt(v, f),
+            DecodedValue::UInt(v) => fmt::Display::fmt(v, f),
+            DecodedValue::Float(v) => fmt::Display::fmt(v, f),
+            DecodedValue::Str(v) => fmt::Display::fmt(v, f),
+            DecodedValue::Bytes(v) => {
+                write!(f, "0x")?;
+                for b in v {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+            DecodedValue::Struct(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+type Decoder = Arc<dyn Fn(DataType, &[u8]) -> Option<DecodedValue> + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct Decoders(RwLock<HashMap<FourCharCode, Decoder>>);
+
+impl Decoders {
+    fn get(&self, id: FourCharCode) -> Option<Decoder> {
+        self.0.read().unwrap().get(&id).cloned()
+    }
+}
+
+fn decode_builtin(val: &SMCVal) -> Option<DecodedValue> {
+    let data_type = val.data_type();
+    let bytes = *val.bytes();
+    match data_type.known()? {
+        KnownType::Flag => bool::from_smc(data_type, bytes)
+            .ok()
+            .map(DecodedValue::Bool),
+        KnownType::U8 | KnownType::U16 | KnownType::U32 | KnownType::U64 => {
+            u64::from_smc(data_type, bytes).ok().map(DecodedValue::UInt)
+        }
+        KnownType::I8 | KnownType::I16 | KnownType::I32 | KnownType::I64 => {
+            i64::from_smc(data_type, bytes).ok().map(DecodedValue::Int)
+        }
+        KnownType::Ch8 => SMCString::from_smc(data_type, bytes)
+            .ok()
+            .map(|s| DecodedValue::Str(s.0)),
+        KnownType::Hex => Some(DecodedValue::Bytes(val.data().to_vec())),
+        KnownType::Struct(id) => decode_struct(id, data_type, bytes),
+        _ => f64::from_smc(data_type, bytes)
+            .ok()
+            .map(DecodedValue::Float),
+    }
+}
+
+fn decode_struct(id: FourCharCode, data_type: DataType, bytes: SMCBytes) -> Option<DecodedValue> {
+    fn field<V: Into<DecodedValue>>(name: &str, value: V) -> (String, DecodedValue) {
+        (name.to_string(), value.into())
+    }
+
+    let fields = if id == TYPE_FAN {
+        let desc = FanDescriptor::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("type", bytes.0[0]),
+            field("zone", desc.zone),
+            field("location", bytes.0[2]),
+            field("rotation", desc.rotation),
+            field("name", desc.name),
+        ]
+    } else if id == TYPE_REV {
+        let rev = Revision::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("major", rev.major),
+            field("minor", rev.minor),
+            field("build", rev.build),
+            field("reserved", rev.reserved),
+            field("release", rev.release),
+        ]
+    } else if id == TYPE_HDI {
+        let info = DriveBayInfo::from_smc(data_type, bytes).ok()?;
+        let sensor = match info.sensor {
+            Some(key) => DecodedValue::Str(key.to_string()),
+            None => DecodedValue::Str(String::new()),
+        };
+        vec![
+            field("bay", info.bay),
+            field("present", info.present),
+            ("sensor".to_string(), sensor),
+        ]
+    } else if id == TYPE_LIM {
+        let limits = ThermalLimits::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("cpu", limits.cpu),
+            field("gpu", limits.gpu),
+            field("memory", limits.memory),
+        ]
+    } else if id == TYPE_LKB || id == TYPE_LKS {
+        let backlight = KeyboardBacklight::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("brightness", backlight.brightness),
+            field("flags", backlight.flags),
+        ]
+    } else if id == TYPE_MSS {
+        let status = MotionSensorStatus::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("enabled", status.enabled),
+            field("triggered", status.triggered),
+        ]
+    } else if id == TYPE_ALI {
+        let info = AmbientLightInfo::from_smc(data_type, bytes).ok()?;
+        vec![
+            field("sensor_type", info.sensor_type),
+            field("cpu_affected", info.cpu_affected),
+        ]
+    } else if id == TYPE_ALP {
+        let params = AmbientLightParams::from_smc(data_type, bytes).ok()?;
+        vec![field("gain", params.gain), field("offset", params.offset)]
+    } else {
+        return None;
+    };
+
+    Some(DecodedValue::Struct(fields))
+}
+
+impl SMCVal {
+    pub fn decode(&self) -> DecodedValue {
+        decode_builtin(self).unwrap_or_else(|| DecodedValue::Bytes(self.data().to_vec()))
+    }
+}
+
+impl SMC {
+    pub fn register_decoder<F>(&self, data_type: FourCharCode, decoder: F)
+    where
+        F: Fn(DataType, &[u8]) -> Option<DecodedValue> + Send + Sync + 'static,
+    {
+        (self.0)
+            .decoders
+            .0
+            .write()
+            .unwrap()
+            .insert(data_type, Arc::new(decoder));
+    }
+
+    pub fn unregister_decoder(&self, data_type: FourCharCode) -> bool {
+        (self.0)
+            .decoders
+            .0
+            .write()
+            .unwrap()
+            .remove(&data_type)
+            .is_some()
+    }
+
+    pub fn read_dynamic(&self, key: FourCharCode) -> Result<DecodedValue, SMCError> {
+        let (data_type, val) = self.read_key_raw(key)?;
+        if let Some(value) = decode_builtin(&val) {
+            return Ok(value);
+        }
+
+        if let Some(decoder) = self.0.decoders.get(data_type.id) {
+            if let Some(value) = decoder(data_type, val.data()) {
+                return Ok(value);
+            }
+        }
+
+        Ok(DecodedValue::Bytes(val.data().to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(id: FourCharCode, data: &[u8]) -> DecodedValue {
+        let data_type = DataType {
+            id,
+            size: data.len() as u32,
+        };
+        SMCVal::new(data_type, data).unwrap().decode()
+    }
+
+    fn fields(fields: &[(&str, DecodedValue)]) -> DecodedValue {
+        DecodedValue::Struct(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn keyboard_backlight() {
+        let expected = fields(&[
+            ("brightness", DecodedValue::UInt(0x80)),
+            ("flags", DecodedValue::UInt(1)),
+        ]);
+        assert_eq!(decode(TYPE_LKB, &[0x80, 0x01]), expected);
+        assert_eq!(decode(TYPE_LKS, &[0x80, 0x01]), expected);
+        assert_eq!(expected.to_string(), "{brightness: 128, flags: 1}");
+    }
+
+    #[test]
+    fn motion_sensor_status() {
+        assert_eq!(
+            decode(TYPE_MSS, &[0x01, 0x00]),
+            fields(&[
+                ("enabled", DecodedValue::Bool(true)),
+                ("triggered", DecodedValue::Bool(false)),
+            ])
+        );
+    }
+
+    #[test]
+    fn ambient_light_info() {
+        assert_eq!(
+            decode(TYPE_ALI, &[0x03, 0x01]),
+            fields(&[
+                ("sensor_type", DecodedValue::UInt(3)),
+                ("cpu_affected", DecodedValue::Bool(true)),
+            ])
+        );
+    }
+
+    #[test]
+    fn ambient_light_params() {
+        assert_eq!(
+            decode(TYPE_ALP, &[0x01, 0x00, 0x00, 0x20]),
+            fields(&[
+                ("gain", DecodedValue::UInt(256)),
+                ("offset", DecodedValue::UInt(32)),
+            ])
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    conversions::TYPE_FLAG,
+    keydb::{self, KeyCategory},
+    KeysInCategory, SMCError, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectricalReading {
+    pub key: FourCharCode,
+    pub label: String,
+    pub value: f64,
+}
+
+pub struct ElectricalReadings {
+    smc: SMC,
+    keys: KeysInCategory,
+}
+
+impl Iterator for ElectricalReadings {
+    type Item = Result<ElectricalReading, SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let key = match self.keys.next()? {
+                Ok(key) => key,
+                Err(err) => return Some(Err(err)),
+            };
+
+            let value = match self.smc.read_key_raw(key) {
+                Ok((data_type, _)) if data_type.id == TYPE_FLAG => None,
+                Ok((_, val)) => val.as_f64(),
+                Err(err) => match err.kind() {
+                    SMCError::NotReadable(_) => None,
+                    _ => return Some(Err(err)),
+                },
+            };
+
+            if let Some(value) = value {
+                let label = match keydb::lookup(key) {
+                    Some(desc) => desc.description.to_string(),
+                    None => key.to_string(),
+                };
+
+                return Some(Ok(ElectricalReading { key, label, value }));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.keys.size_hint().1)
+    }
+}
+
+impl SMC {
+    fn electrical_readings(&self, category: KeyCategory) -> Result<ElectricalReadings, SMCError> {
+        Ok(ElectricalReadings {
+            smc: self.clone(),
+            keys: self.keys_in_category(category)?,
+        })
+    }
+
+    pub fn voltages(&self) -> Result<ElectricalReadings, SMCError> {
+        self.electrical_readings(KeyCategory::Voltage)
+    }
+
+    pub fn currents(&self) -> Result<ElectricalReadings, SMCError> {
+        self.electrical_readings(KeyCategory::Current)
+    }
+}
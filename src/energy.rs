@@ -0,0 +1,133 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PowerSource {
+    Key(FourCharCode),
+    VoltageCurrent(FourCharCode, FourCharCode),
+}
+
+pub struct EnergyMonitor {
+    smc: SMC,
+    source: PowerSource,
+    interval: Duration,
+    joules: f64,
+    elapsed: Duration,
+    samples: usize,
+    last: Option<(Instant, f64)>,
+}
+
+impl EnergyMonitor {
+    pub fn new(smc: &SMC, source: PowerSource, interval: Duration) -> EnergyMonitor {
+        EnergyMonitor {
+            smc: smc.clone(),
+            source,
+            interval,
+            joules: 0.0,
+            elapsed: Duration::from_secs(0),
+            samples: 0,
+            last: None,
+        }
+    }
+
+    #[inline]
+    pub fn source(&self) -> PowerSource {
+        self.source
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn read_watts(&self) -> Result<f64, SMCError> {
+        match self.source {
+            PowerSource::Key(key) => self.smc.read_key(key),
+            PowerSource::VoltageCurrent(volts, amps) => {
+                Ok(self.smc.read_key::<f64>(volts)? * self.smc.read_key::<f64>(amps)?)
+            }
+        }
+    }
+
+    pub fn sample(&mut self) -> Result<f64, SMCError> {
+        let watts = self.read_watts()?;
+        let now = Instant::now();
+
+        if let Some((then, last_watts)) = self.last {
+            let dt = now.duration_since(then);
+            let secs = dt.as_secs() as f64 + f64::from(dt.subsec_nanos()) / 1e9;
+            self.joules += (last_watts + watts) / 2.0 * secs;
+            self.elapsed += dt;
+        }
+
+        self.last = Some((now, watts));
+        self.samples += 1;
+
+        Ok(watts)
+    }
+
+    pub fn run_for(&mut self, duration: Duration) -> Result<(), SMCError> {
+        let deadline = Instant::now() + duration;
+
+        self.sample()?;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+
+            let remaining = deadline - now;
+            thread::sleep(if remaining < self.interval {
+                remaining
+            } else {
+                self.interval
+            });
+            self.sample()?;
+        }
+    }
+
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    #[inline]
+    pub fn total_energy(&self) -> f64 {
+        self.joules
+    }
+
+    pub fn watt_hours(&self) -> f64 {
+        self.joules / 3600.0
+    }
+
+    pub fn average_watts(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs() as f64 + f64::from(self.elapsed.subsec_nanos()) / 1e9;
+        if secs > 0.0 {
+            Some(self.joules / secs)
+        } else {
+            self.last.map(|(_, watts)| watts)
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.joules = 0.0;
+        self.elapsed = Duration::from_secs(0);
+        self.samples = 0;
+        self.last = None;
+    }
+}
+
+impl SMC {
+    pub fn energy_monitor(&self, source: PowerSource, interval: Duration) -> EnergyMonitor {
+        EnergyMonitor::new(self, source, interval)
+    }
+}
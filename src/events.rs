@@ -0,0 +1,191 @@
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::Duration;
+
+use crate::{iokit::nth_matching_service, sys::*, SMCError, SMC};
+
+// iokit_family_msg(sub_iokit_pmu, 0x100), kIOPMMessageBatteryStatusHasChanged
+const MESSAGE_BATTERY_STATUS_CHANGED: u32 = 0xe001_0100;
+// iokit_family_msg(sub_iokit_powermanagement, 0x130), kIOPMMessageSystemPowerEventOccurred
+const MESSAGE_SYSTEM_POWER_EVENT: u32 = 0xe003_4130;
+// iokit_common_msg(0x010), kIOMessageServiceIsTerminated
+const MESSAGE_SERVICE_TERMINATED: u32 = 0xe000_0010;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SMCEventKind {
+    /// Battery or charger state changed, e.g. the adapter was plugged in.
+    Charger,
+    /// Thermal or power limit state changed, e.g. a new thermal warning level.
+    Thermal,
+    /// The SMC service went away; no further events will be delivered.
+    Terminated,
+    Other(u32),
+}
+
+impl From<u32> for SMCEventKind {
+    fn from(message_type: u32) -> SMCEventKind {
+        match message_type {
+            MESSAGE_BATTERY_STATUS_CHANGED => SMCEventKind::Charger,
+            MESSAGE_SYSTEM_POWER_EVENT => SMCEventKind::Thermal,
+            MESSAGE_SERVICE_TERMINATED => SMCEventKind::Terminated,
+            other => SMCEventKind::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SMCEvent {
+    pub message_type: u32,
+    pub argument: usize,
+}
+
+impl SMCEvent {
+    #[inline]
+    pub fn kind(&self) -> SMCEventKind {
+        SMCEventKind::from(self.message_type)
+    }
+}
+
+extern "C" fn interest_callback(
+    refcon: *mut c_void,
+    _service: io_service_t,
+    message_type: u32,
+    argument: *mut c_void,
+) {
+    let tx = unsafe { &*(refcon as *const Sender<SMCEvent>) };
+    let _ = tx.send(SMCEvent {
+        message_type,
+        argument: argument as usize,
+    });
+}
+
+extern "C" fn drain(_context: *mut c_void) {}
+
+pub struct Events {
+    rx: Receiver<SMCEvent>,
+    tx: *mut Sender<SMCEvent>,
+    port: IONotificationPortRef,
+    queue: dispatch_queue_t,
+    notification: io_object_t,
+}
+
+impl Events {
+    fn new(service: &CStr, index: usize) -> Result<Events, SMCError> {
+        let device = nth_matching_service(service, index)?;
+
+        let (tx, rx) = mpsc::channel();
+        let tx = Box::into_raw(Box::new(tx));
+
+        unsafe {
+            let port = IONotificationPortCreate(MACH_PORT_NULL);
+            if port.is_null() {
+                IOObjectRelease(device);
+                drop(Box::from_raw(tx));
+                return Err(SMCError::FailedToOpen);
+            }
+            let queue = dispatch_queue_create(b"smc.events\0".as_ptr() as *const _, ptr::null());
+            if queue.is_null() {
+                IONotificationPortDestroy(port);
+                IOObjectRelease(device);
+                drop(Box::from_raw(tx));
+                return Err(SMCError::FailedToOpen);
+            }
+            IONotificationPortSetDispatchQueue(port, queue);
+
+            let mut notification: io_object_t = ptr::null_mut();
+            let result = IOServiceAddInterestNotification(
+                port,
+                device,
+                b"IOGeneralInterest\0".as_ptr() as *const _,
+                interest_callback,
+                tx as *mut c_void,
+                &mut notification,
+            );
+            IOObjectRelease(device);
+
+            let events = Events {
+                rx,
+                tx,
+                port,
+                queue,
+                notification,
+            };
+
+            if result != KERN_SUCCESS {
+                return Err(SMCError::Unknown(result, 0));
+            }
+
+            Ok(events)
+        }
+    }
+
+    pub fn try_next(&self) -> Option<SMCEvent> {
+        match self.rx.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    pub fn next_timeout(&self, timeout: Duration) -> Option<SMCEvent> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl Iterator for Events {
+    type Item = SMCEvent;
+
+    fn next(&mut self) -> Option<SMCEvent> {
+        self.rx.recv().ok()
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.notification.is_null() {
+                IOObjectRelease(self.notification);
+            }
+            IONotificationPortDestroy(self.port);
+            dispatch_sync_f(self.queue, ptr::null_mut(), drain);
+            dispatch_release(self.queue);
+            drop(Box::from_raw(self.tx));
+        }
+    }
+}
+
+unsafe impl Send for Events {}
+
+impl SMC {
+    /// Subscribes to notifications from the IOService this handle is
+    /// connected to. Fails with `UnsupportedFeature` for backends that are
+    /// not backed by IOKit.
+    pub fn events(&self) -> Result<Events, SMCError> {
+        match self.0.backend.service() {
+            Some((service, index)) => Events::new(service, index),
+            None => Err(SMCError::UnsupportedFeature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_kind() {
+        let event = |message_type| SMCEvent {
+            message_type,
+            argument: 0,
+        };
+
+        assert_eq!(event(0xe001_0100).kind(), SMCEventKind::Charger);
+        assert_eq!(event(0xe003_4130).kind(), SMCEventKind::Thermal);
+        assert_eq!(event(0xe000_0010).kind(), SMCEventKind::Terminated);
+        assert_eq!(event(0xe000_0280).kind(), SMCEventKind::Other(0xe000_0280));
+    }
+}
@@ -0,0 +1,225 @@
+//! Serialized snapshot schema.
+//!
+//! A snapshot is stored as a document with a `version` (currently `1`) and a
+//! list of `keys`. Each key entry has:
+//!
+//! - `key`: the four character code, e.g. `"TC0P"` or `"FS! "`
+//! - `type`: the four character data type, e.g. `"sp78"`
+//! - `size`: the data size in bytes
+//! - `attributes`: the raw key attribute bits
+//! - `data`: the value as lowercase hex, omitted when the key was not readable
+//!
+//! The `serde` feature pulls in `serde_derive` and `serde_json` (Rust 1.71)
+//! and `toml` (Rust 1.66), so it raises the crate's minimum Rust version.
+//!
+//! ```json
+//! {"version": 1, "keys": [{"key": "TC0P", "type": "sp78", "size": 2, "attributes": 192, "data": "3d80"}]}
+//! ```
+
+use crate::{DataType, KeyAttributes, KeyInfo, SMCBytes, SMCVal, Snapshot, SnapshotEntry};
+
+use four_char_code::FourCharCode;
+use serde_crate::{de::Error, Deserialize, Serialize};
+
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct SnapshotDoc {
+    version: u32,
+    keys: Vec<EntryDoc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct EntryDoc {
+    key: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    size: u32,
+    attributes: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+fn parse_fcc(s: &str) -> Result<FourCharCode, String> {
+    if s.len() == 4 {
+        Ok(FourCharCode::from(s))
+    } else {
+        Err(format!("{:?} is not a valid four char code", s))
+    }
+}
+
+fn parse_hex(s: &str) -> Result<SMCBytes, String> {
+    let mut bytes = SMCBytes::default();
+    if s.len() % 2 != 0 || s.len() / 2 > bytes.0.len() {
+        return Err(format!("{:?} is not valid key data", s));
+    }
+
+    for (i, byte) in bytes.0.iter_mut().take(s.len() / 2).enumerate() {
+        *byte = s
+            .get(i * 2..i * 2 + 2)
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            .ok_or_else(|| format!("{:?} is not valid key data", s))?;
+    }
+    Ok(bytes)
+}
+
+impl SnapshotDoc {
+    fn new(snapshot: &Snapshot) -> SnapshotDoc {
+        SnapshotDoc {
+            version: SCHEMA_VERSION,
+            keys: snapshot
+                .iter()
+                .map(|entry| EntryDoc {
+                    key: entry.key.to_string(),
+                    data_type: entry.info.data_type.id.to_string(),
+                    size: entry.info.data_type.size,
+                    attributes: entry.info.attributes.bits(),
+                    data: entry
+                        .data()
+                        .map(|data| data.iter().map(|b| format!("{:02x}", b)).collect()),
+                })
+                .collect(),
+        }
+    }
+
+    fn into_snapshot(self) -> Result<Snapshot, String> {
+        if self.version != SCHEMA_VERSION {
+            return Err(format!("unsupported snapshot version {}", self.version));
+        }
+
+        let mut res = Snapshot::default();
+        for entry in self.keys {
+            let data_type = DataType {
+                id: parse_fcc(&entry.data_type)?,
+                size: entry.size,
+            };
+            let value = match entry.data {
+                Some(ref data) if data.len() != entry.size as usize * 2 => {
+                    return Err(format!(
+                        "{} data {:?} does not match its size {}",
+                        entry.key, data, entry.size
+                    ));
+                }
+                Some(ref data) => Some(SMCVal {
+                    data_type,
+                    bytes: parse_hex(data)?,
+                }),
+                None => None,
+            };
+
+            res.insert(SnapshotEntry {
+                key: parse_fcc(&entry.key)?,
+                info: KeyInfo {
+                    data_type,
+                    attributes: KeyAttributes(entry.attributes),
+                },
+                value,
+            });
+        }
+
+        Ok(res)
+    }
+}
+
+impl Snapshot {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&SnapshotDoc::new(self))
+    }
+
+    pub fn from_json(s: &str) -> Result<Snapshot, serde_json::Error> {
+        serde_json::from_str::<SnapshotDoc>(s)?
+            .into_snapshot()
+            .map_err(serde_json::Error::custom)
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(&SnapshotDoc::new(self))
+    }
+
+    pub fn from_toml(s: &str) -> Result<Snapshot, toml::de::Error> {
+        toml::from_str::<SnapshotDoc>(s)?
+            .into_snapshot()
+            .map_err(toml::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryBackend, SMC};
+
+    use four_char_code::four_char_code;
+
+    fn snapshot() -> Snapshot {
+        let backend = MemoryBackend::new();
+        backend
+            .insert(
+                four_char_code!("TC0P"),
+                DataType {
+                    id: four_char_code!("sp78"),
+                    size: 2,
+                },
+                61.5,
+            )
+            .unwrap();
+        backend
+            .insert(
+                four_char_code!("FS! "),
+                DataType {
+                    id: four_char_code!("ui16"),
+                    size: 2,
+                },
+                3_u16,
+            )
+            .unwrap();
+        backend
+            .insert(
+                four_char_code!("MSAL"),
+                DataType {
+                    id: four_char_code!("ui8 "),
+                    size: 1,
+                },
+                0_u8,
+            )
+            .unwrap();
+        backend.set_attributes(four_char_code!("MSAL"), KeyAttributes::WRITE);
+        SMC::with_backend(backend).snapshot().unwrap()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let snapshot = snapshot();
+        let restored = Snapshot::from_json(&snapshot.to_json().unwrap()).unwrap();
+
+        assert_eq!(restored.len(), 3);
+        assert!(snapshot.diff(&restored).is_empty());
+        assert_eq!(restored.get(four_char_code!("MSAL")).unwrap().data(), None);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let snapshot = snapshot();
+        let restored = Snapshot::from_toml(&snapshot.to_toml().unwrap()).unwrap();
+
+        assert_eq!(restored.len(), 3);
+        assert!(snapshot.diff(&restored).is_empty());
+    }
+
+    #[test]
+    fn data_must_match_size() {
+        let doc = r#"{"version": 1, "keys": [{"key": "TC0P", "type": "sp78", "size": 2, "attributes": 192, "data": "3d"}]}"#;
+        assert!(Snapshot::from_json(doc).is_err());
+
+        let doc = doc.replace("\"3d\"", "\"3d8000\"");
+        assert!(Snapshot::from_json(&doc).is_err());
+
+        let doc = doc.replace("\"3d8000\"", "\"3d80\"");
+        let snapshot = Snapshot::from_json(&doc).unwrap();
+        assert_eq!(
+            snapshot.get(four_char_code!("TC0P")).unwrap().data(),
+            Some(&[0x3d, 0x80][..])
+        );
+    }
+}
@@ -0,0 +1,1006 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{
+    conversions::SMCType,
+    keys::consts::{FAN_COUNT, FAN_TEST, FORCED_FANS},
+    KeyIndex, SMCError, SMCRepr, SMCVal, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FanMode {
+    Auto = 0,
+    Forced = 1,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FanType {
+    PwmTach,
+    Rpm,
+    PumpPwm,
+    PumpRpm,
+    PwmNoTach,
+    Empty,
+    Unknown(u8),
+}
+
+impl From<u8> for FanType {
+    fn from(value: u8) -> FanType {
+        match value {
+            0 => FanType::PwmTach,
+            1 => FanType::Rpm,
+            2 => FanType::PumpPwm,
+            3 => FanType::PumpRpm,
+            4 => FanType::PwmNoTach,
+            0xff => FanType::Empty,
+            other => FanType::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FanLocation {
+    LeftLowerFront,
+    CenterLowerFront,
+    RightLowerFront,
+    LeftMidFront,
+    CenterMidFront,
+    RightMidFront,
+    LeftUpperFront,
+    CenterUpperFront,
+    RightUpperFront,
+    LeftLowerRear,
+    CenterLowerRear,
+    RightLowerRear,
+    LeftMidRear,
+    CenterMidRear,
+    RightMidRear,
+    LeftUpperRear,
+    CenterUpperRear,
+    RightUpperRear,
+    Unknown(u8),
+}
+
+impl From<u8> for FanLocation {
+    fn from(value: u8) -> FanLocation {
+        match value {
+            0 => FanLocation::LeftLowerFront,
+            1 => FanLocation::CenterLowerFront,
+            2 => FanLocation::RightLowerFront,
+            3 => FanLocation::LeftMidFront,
+            4 => FanLocation::CenterMidFront,
+            5 => FanLocation::RightMidFront,
+            6 => FanLocation::LeftUpperFront,
+            7 => FanLocation::CenterUpperFront,
+            8 => FanLocation::RightUpperFront,
+            9 => FanLocation::LeftLowerRear,
+            10 => FanLocation::CenterLowerRear,
+            11 => FanLocation::RightLowerRear,
+            12 => FanLocation::LeftMidRear,
+            13 => FanLocation::CenterMidRear,
+            14 => FanLocation::RightMidRear,
+            15 => FanLocation::LeftUpperRear,
+            16 => FanLocation::CenterUpperRear,
+            17 => FanLocation::RightUpperRear,
+            other => FanLocation::Unknown(other),
+        }
+    }
+}
+
+impl FanLocation {
+    fn index(self) -> Option<u8> {
+        match self {
+            FanLocation::Unknown(_) => None,
+            FanLocation::LeftLowerFront => Some(0),
+            FanLocation::CenterLowerFront => Some(1),
+            FanLocation::RightLowerFront => Some(2),
+            FanLocation::LeftMidFront => Some(3),
+            FanLocation::CenterMidFront => Some(4),
+            FanLocation::RightMidFront => Some(5),
+            FanLocation::LeftUpperFront => Some(6),
+            FanLocation::CenterUpperFront => Some(7),
+            FanLocation::RightUpperFront => Some(8),
+            FanLocation::LeftLowerRear => Some(9),
+            FanLocation::CenterLowerRear => Some(10),
+            FanLocation::RightLowerRear => Some(11),
+            FanLocation::LeftMidRear => Some(12),
+            FanLocation::CenterMidRear => Some(13),
+            FanLocation::RightMidRear => Some(14),
+            FanLocation::LeftUpperRear => Some(15),
+            FanLocation::CenterUpperRear => Some(16),
+            FanLocation::RightUpperRear => Some(17),
+        }
+    }
+
+    pub fn is_front(self) -> bool {
+        match self.index() {
+            Some(i) => i < 9,
+            None => false,
+        }
+    }
+
+    pub fn is_rear(self) -> bool {
+        match self.index() {
+            Some(i) => i >= 9,
+            None => false,
+        }
+    }
+
+    pub fn is_left(self) -> bool {
+        match self.index() {
+            Some(i) => i % 3 == 0,
+            None => false,
+        }
+    }
+
+    pub fn is_center(self) -> bool {
+        match self.index() {
+            Some(i) => i % 3 == 1,
+            None => false,
+        }
+    }
+
+    pub fn is_right(self) -> bool {
+        match self.index() {
+            Some(i) => i % 3 == 2,
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThermalZone {
+    Cpu,
+    Gpu,
+    Unknown(u8),
+}
+
+impl From<u8> for ThermalZone {
+    fn from(value: u8) -> ThermalZone {
+        match value {
+            0 => ThermalZone::Cpu,
+            1 => ThermalZone::Gpu,
+            x => ThermalZone::Unknown(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanDescriptor {
+    pub fan_type: FanType,
+    pub zone: u8,
+    pub location: FanLocation,
+    pub rotation: u8,
+    pub name: String,
+}
+
+impl FanDescriptor {
+    #[inline]
+    pub fn thermal_zone(&self) -> ThermalZone {
+        ThermalZone::from(self.zone)
+    }
+}
+
+/// Snapshot of the `FS! ` forced-fan bitmask.
+///
+/// Besides the bits themselves, a `ManagedFans` remembers which fans were
+/// changed through `set_managed` (or the constructors), so `apply` only
+/// touches those and leaves concurrent changes to other fans alone.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ManagedFans {
+    bits: u16,
+    forced: u16,
+    released: u16,
+}
+
+impl PartialEq for ManagedFans {
+    fn eq(&self, other: &ManagedFans) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl Eq for ManagedFans {}
+
+impl std::hash::Hash for ManagedFans {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state)
+    }
+}
+
+impl ManagedFans {
+    #[inline]
+    pub fn all() -> ManagedFans {
+        ManagedFans::from_forced_bits(0)
+    }
+
+    #[inline]
+    pub fn none() -> ManagedFans {
+        ManagedFans::from_forced_bits(u16::max_value())
+    }
+
+    #[inline]
+    pub fn from_forced_bits(bits: u16) -> ManagedFans {
+        ManagedFans {
+            bits,
+            forced: bits,
+            released: !bits,
+        }
+    }
+
+    #[inline]
+    pub fn forced_bits(self) -> u16 {
+        self.bits
+    }
+
+    fn mask(id: u32) -> u16 {
+        if id < 16 {
+            1_u16 << id
+        } else {
+            0
+        }
+    }
+
+    pub fn is_managed(self, id: u32) -> bool {
+        self.bits & ManagedFans::mask(id) == 0
+    }
+
+    pub fn set_managed(&mut self, id: u32, managed: bool) {
+        let mask = ManagedFans::mask(id);
+        if managed {
+            self.bits &= !mask;
+            self.forced &= !mask;
+            self.released |= mask;
+        } else {
+            self.bits |= mask;
+            self.forced |= mask;
+            self.released &= !mask;
+        }
+    }
+
+    /// Writes the fans changed on this snapshot back to the SMC. Fans that
+    /// were not touched keep whatever state the SMC currently reports.
+    pub fn apply(&self, smc: &SMC) -> Result<(), SMCError> {
+        let (forced, released) = (self.forced, self.released);
+        ManagedFans::update(&smc.0, |fans| {
+            fans.bits = (fans.bits | forced) & !released;
+        })
+    }
+
+    fn read(smc_repr: &SMCRepr) -> Result<ManagedFans, SMCError> {
+        smc_repr.read_key(FORCED_FANS).map(|bits| ManagedFans {
+            bits,
+            forced: 0,
+            released: 0,
+        })
+    }
+
+    fn update<F>(smc_repr: &SMCRepr, f: F) -> Result<(), SMCError>
+    where
+        F: FnOnce(&mut ManagedFans),
+    {
+        let _guard = smc_repr.forced_fans.lock().unwrap();
+        let old = ManagedFans::read(smc_repr)?;
+        let mut new = old;
+        f(&mut new);
+
+        if old != new {
+            smc_repr.write_key(FORCED_FANS, new.bits)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+const HEALTH_SAMPLES: usize = 3;
+const HEALTH_INTERVAL_MS: u64 = 250;
+const UNDERSPEED_RATIO: f64 = 0.8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FanHealth {
+    Ok,
+    Stalled,
+    Underspeed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanInfo {
+    pub id: u32,
+    pub name: String,
+    pub mode: FanMode,
+    pub current_speed: f64,
+    pub min_speed: f64,
+    pub max_speed: f64,
+    pub target_speed: Option<f64>,
+}
+
+pub struct Fan {
+    pub(crate) smc_repr: Arc<SMCRepr>,
+    pub(crate) id: u32,
+    pub(crate) index: KeyIndex,
+    pub(crate) name: String,
+}
+
+impl fmt::Debug for Fan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fan")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl Clone for Fan {
+    fn clone(&self) -> Fan {
+        Fan {
+            smc_repr: self.smc_repr.clone(),
+            id: self.id,
+            index: self.index,
+            name: self.name.clone(),
+        }
+    }
+}
+
+impl Fan {
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn name_bytes(&self) -> &[u8] {
+        self.name.as_bytes()
+    }
+
+    pub fn descriptor(&self) -> Result<FanDescriptor, SMCError> {
+        self.smc_repr.read_key(fcc_format!("F{}ID", self.index))
+    }
+
+    pub fn thermal_zone(&self) -> Result<ThermalZone, SMCError> {
+        Ok(self.descriptor()?.thermal_zone())
+    }
+
+    pub fn min_speed(&self) -> Result<f64, SMCError> {
+        self.smc_repr.read_key(fcc_format!("F{}Mn", self.index))
+    }
+
+    pub fn max_speed(&self) -> Result<f64, SMCError> {
+        self.smc_repr.read_key(fcc_format!("F{}Mx", self.index))
+    }
+
+    pub fn current_speed(&self) -> Result<f64, SMCError> {
+        self.smc_repr.read_key(fcc_format!("F{}Ac", self.index))
+    }
+
+    pub fn target_speed(&self) -> Result<f64, SMCError> {
+        self.smc_repr.read_key(fcc_format!("F{}Tg", self.index))
+    }
+
+    fn target_speed_opt(&self) -> Result<Option<f64>, SMCError> {
+        match self.target_speed() {
+            Ok(target) => Ok(Some(target)),
+            Err(ref err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn info(&self) -> Result<FanInfo, SMCError> {
+        Ok(FanInfo {
+            id: self.id,
+            name: self.name.clone(),
+            mode: self.mode()?,
+            current_speed: self.current_speed()?,
+            min_speed: self.min_speed()?,
+            max_speed: self.max_speed()?,
+            target_speed: self.target_speed_opt()?,
+        })
+    }
+
+    pub fn health(&self) -> Result<FanHealth, SMCError> {
+        self.health_over(HEALTH_SAMPLES, Duration::from_millis(HEALTH_INTERVAL_MS))
+    }
+
+    pub fn health_over(&self, samples: usize, interval: Duration) -> Result<FanHealth, SMCError> {
+        match self
+            .smc_repr
+            .read_key::<u8>(fcc_format!("F{}St", self.index))
+        {
+            Ok(0) => (),
+            Err(ref err) if err.is_not_found() => (),
+            Ok(_) => return Ok(FanHealth::Stalled),
+            Err(err) => return Err(err),
+        }
+
+        // An idle fan under automatic control may be parked at 0 rpm with no
+        // target at all; that is not a stall.
+        let target = self.target_speed_opt()?.unwrap_or(0.0);
+        if target <= 0.0 && self.mode()? == FanMode::Auto {
+            return Ok(FanHealth::Ok);
+        }
+
+        let min = self.min_speed()?;
+        let expected = if target > min { target } else { min };
+        if expected <= 0.0 {
+            return Ok(FanHealth::Ok);
+        }
+
+        let samples = if samples == 0 { 1 } else { samples };
+        let mut total = 0.0;
+        let mut stalled = true;
+        for i in 0..samples {
+            if i > 0 {
+                thread::sleep(interval);
+            }
+            let current = self.current_speed()?;
+            if current > 0.0 {
+                stalled = false;
+            }
+            total += current;
+        }
+
+        if stalled {
+            Ok(FanHealth::Stalled)
+        } else if total / (samples as f64) < expected * UNDERSPEED_RATIO {
+            Ok(FanHealth::Underspeed)
+        } else {
+            Ok(FanHealth::Ok)
+        }
+    }
+
+    pub fn rpm(&self) -> Result<f64, SMCError> {
+        let mut rpm = self.current_speed()? - self.min_speed()?;
+        if rpm < 0.0 {
+            rpm = 0.0;
+        }
+
+        Ok(rpm)
+    }
+
+    pub fn mode(&self) -> Result<FanMode, SMCError> {
+        match self
+            .smc_repr
+            .read_key::<u8>(fcc_format!("F{}Md", self.index))
+        {
+            Ok(0) => return Ok(FanMode::Auto),
+            Ok(_) => return Ok(FanMode::Forced),
+            Err(ref err) if err.is_not_found() => (),
+            Err(err) => return Err(err),
+        }
+
+        if ManagedFans::read(&self.smc_repr)?.is_managed(self.id) {
+            Ok(FanMode::Auto)
+        } else {
+            Ok(FanMode::Forced)
+        }
+    }
+
+    fn set_unlocked(&self, what: bool) -> Result<(), SMCError> {
+        match self.smc_repr.write_key(FAN_TEST, what as u8) {
+            Err(ref err) if err.is_not_found() => Ok(()),
+            res => res,
+        }
+    }
+
+    fn others_forced(&self) -> Result<bool, SMCError> {
+        let count = match self.smc_repr.read_key::<u8>(FAN_COUNT) {
+            Ok(count) => u32::from(count),
+            Err(ref err) if err.is_not_found() => return Ok(false),
+            Err(err) => return Err(err),
+        };
+
+        for id in (0..count).filter(|id| *id != self.id) {
+            let index = KeyIndex::try_from(id)?;
+            match self.smc_repr.read_key::<u8>(fcc_format!("F{}Md", index)) {
+                Ok(0) => (),
+                Ok(_) => return Ok(true),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn set_mode(&self, mode: FanMode) -> Result<(), SMCError> {
+        {
+            // `Ftst` unlocks manual control for every fan at once, so it may
+            // only be cleared once the last forced fan goes back to auto.
+            let _guard = self.smc_repr.forced_fans.lock().unwrap();
+            if mode == FanMode::Forced {
+                self.set_unlocked(true)?;
+            }
+
+            match self
+                .smc_repr
+                .write_key(fcc_format!("F{}Md", self.index), mode as u8)
+            {
+                Ok(()) if mode == FanMode::Auto => {
+                    return if self.others_forced()? {
+                        Ok(())
+                    } else {
+                        self.set_unlocked(false)
+                    };
+                }
+                Err(ref err) if err.is_not_found() => (),
+                res => return res,
+            }
+        }
+
+        ManagedFans::update(&self.smc_repr, |fans| {
+            fans.set_managed(self.id, mode == FanMode::Auto)
+        })
+    }
+
+    pub fn is_managed(&self) -> Result<bool, SMCError> {
+        Ok(self.mode()? == FanMode::Auto)
+    }
+
+    pub fn set_managed(&self, what: bool) -> Result<(), SMCError> {
+        self.set_mode(if what { FanMode::Auto } else { FanMode::Forced })
+    }
+
+    pub fn set_min_speed(&self, speed: f64) -> Result<(), SMCError> {
+        let max = self.max_speed()?;
+        if speed <= 0.0 || speed > max {
+            Err(SMCError::UnsafeFanSpeed)
+        } else {
+            self.smc_repr
+                .write_key(fcc_format!("F{}Mn", self.index), speed)
+        }
+    }
+
+    pub fn set_current_speed(&self, speed: f64) -> Result<(), SMCError> {
+        let min = self.min_speed()?;
+        let max = self.max_speed()?;
+        if speed <= min || speed > max {
+            Err(SMCError::UnsafeFanSpeed)
+        } else {
+            self.set_managed(false)?;
+            self.smc_repr
+                .write_key(fcc_format!("F{}Tg", self.index), speed)
+        }
+    }
+
+    pub fn set_percent(&self, percent: f64) -> Result<(), SMCError> {
+        if percent.is_nan() {
+            return Err(SMCError::UnsafeFanSpeed);
+        }
+        let percent = if percent < 0.0 {
+            0.0
+        } else if percent > 100.0 {
+            100.0
+        } else {
+            percent
+        };
+
+        let min = self.min_speed()?;
+        let max = self.max_speed()?;
+        if max <= min {
+            return Err(SMCError::UnsafeFanSpeed);
+        }
+
+        self.set_managed(false)?;
+        self.smc_repr.write_key(
+            fcc_format!("F{}Tg", self.index),
+            min + (max - min) * percent / 100.0,
+        )
+    }
+
+    pub fn force_speed(&self, speed: f64) -> Result<FanOverrideGuard, SMCError> {
+        let mode = self.mode()?;
+        let target = self.target_speed_opt()?;
+
+        self.set_current_speed(speed)?;
+
+        Ok(FanOverrideGuard {
+            fan: self.clone(),
+            mode,
+            target,
+            restored: false,
+        })
+    }
+
+    pub fn percent(&self) -> Result<f64, SMCError> {
+        let current = self.current_speed()?;
+        let min = self.min_speed()?;
+        let max = self.max_speed()?;
+
+        let rpm = current - min;
+        let rpm = if rpm < 0.0 { 0.0 } else { rpm };
+
+        Ok(rpm / (max - min) * 100.0)
+    }
+}
+
+impl SMC {
+    pub fn managed_fans(&self) -> Result<ManagedFans, SMCError> {
+        ManagedFans::read(&self.0)
+    }
+
+    pub fn refresh_fan_infos(&self, infos: &mut [FanInfo]) -> Result<(), SMCError> {
+        let mut keys: Vec<FourCharCode> = Vec::with_capacity(infos.len() * 5);
+        for info in infos.iter() {
+            let index = KeyIndex::try_from(info.id)?;
+            keys.push(fcc_format!("F{}Md", index));
+            keys.push(fcc_format!("F{}Ac", index));
+            keys.push(fcc_format!("F{}Mn", index));
+            keys.push(fcc_format!("F{}Mx", index));
+            keys.push(fcc_format!("F{}Tg", index));
+        }
+
+        let mut values = keys.iter().zip(self.read_many(&keys));
+        let mut managed: Option<ManagedFans> = None;
+
+        for info in infos.iter_mut() {
+            info.mode = match decode_next::<u8, _>(&mut values) {
+                Ok(0) => FanMode::Auto,
+                Ok(_) => FanMode::Forced,
+                Err(ref err) if err.is_not_found() => {
+                    let fans = match managed {
+                        Some(fans) => fans,
+                        None => {
+                            let fans = self.managed_fans()?;
+                            managed = Some(fans);
+                            fans
+                        }
+                    };
+                    if fans.is_managed(info.id) {
+                        FanMode::Auto
+                    } else {
+                        FanMode::Forced
+                    }
+                }
+                Err(err) => return Err(err),
+            };
+            info.current_speed = decode_next(&mut values)?;
+            info.min_speed = decode_next(&mut values)?;
+            info.max_speed = decode_next(&mut values)?;
+            info.target_speed = match decode_next(&mut values) {
+                Ok(target) => Some(target),
+                Err(ref err) if err.is_not_found() => None,
+                Err(err) => return Err(err),
+            };
+        }
+
+        Ok(())
+    }
+
+    pub fn fans_lossy(&self) -> Result<Vec<Fan>, SMCError> {
+        let len = self.fans_len()?;
+        let mut res: Vec<Fan> = Vec::with_capacity(len);
+
+        for index in KeyIndex::all() {
+            if res.len() >= len {
+                break;
+            }
+
+            match self.fan(u32::from(index.get())) {
+                Ok(fan) => res.push(fan),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn for_each_fan<F>(&self, f: F) -> Result<(), SMCError>
+    where
+        F: Fn(&Fan) -> Result<(), SMCError>,
+    {
+        let mut errors = Vec::new();
+        for fan in self.fans()? {
+            if let Err(err) = f(&fan) {
+                errors.push((fan.id, err));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SMCError::FanErrors(errors))
+        }
+    }
+
+    pub fn set_all_fans_full(&self) -> Result<(), SMCError> {
+        self.for_each_fan(|fan| fan.set_percent(100.0))
+    }
+
+    pub fn restore_fan_auto(&self) -> Result<(), SMCError> {
+        self.for_each_fan(|fan| fan.set_mode(FanMode::Auto))
+    }
+}
+
+fn decode_next<'a, T, I>(values: &mut I) -> Result<T, SMCError>
+where
+    T: SMCType,
+    I: Iterator<Item = (&'a FourCharCode, Result<SMCVal, SMCError>)>,
+{
+    match values.next() {
+        Some((key, val)) => {
+            let val = val?;
+            T::from_smc(val.data_type(), *val.bytes()).map_err(|e| e.for_key(*key))
+        }
+        None => Err(SMCError::BadArgument),
+    }
+}
+
+unsafe impl Send for Fan {}
+unsafe impl Sync for Fan {}
+
+#[derive(Debug)]
+pub struct FanOverrideGuard {
+    fan: Fan,
+    mode: FanMode,
+    target: Option<f64>,
+    restored: bool,
+}
+
+impl FanOverrideGuard {
+    #[inline]
+    pub fn fan(&self) -> &Fan {
+        &self.fan
+    }
+
+    pub fn restore(mut self) -> Result<(), SMCError> {
+        self.restore_previous()
+    }
+
+    fn restore_previous(&mut self) -> Result<(), SMCError> {
+        if self.restored {
+            return Ok(());
+        }
+        self.restored = true;
+
+        if let (FanMode::Forced, Some(target)) = (self.mode, self.target) {
+            self.fan
+                .smc_repr
+                .write_key(fcc_format!("F{}Tg", self.fan.index), target)?;
+        }
+        self.fan.set_mode(self.mode)
+    }
+}
+
+impl Drop for FanOverrideGuard {
+    fn drop(&mut self) {
+        let _ = self.restore_previous();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, KeyAttributes, KeyInfo, MemoryBackend, Platform, SMCBytes};
+
+    use four_char_code::four_char_code;
+
+    const FLT: DataType = DataType {
+        id: four_char_code!("flt "),
+        size: 4,
+    };
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+
+    fn apple_silicon() -> MemoryBackend {
+        let backend = MemoryBackend::with_platform(Platform::AppleSilicon);
+        backend.insert(four_char_code!("FNum"), UI8, 1_u8).unwrap();
+        backend
+            .insert(four_char_code!("F0Ac"), FLT, 2317.5)
+            .unwrap();
+        backend
+            .insert(four_char_code!("F0Mn"), FLT, 1200.0)
+            .unwrap();
+        backend
+            .insert(four_char_code!("F0Mx"), FLT, 4900.0)
+            .unwrap();
+        backend
+            .insert(four_char_code!("F0Tg"), FLT, 2300.0)
+            .unwrap();
+        backend.insert(four_char_code!("F0Md"), UI8, 0_u8).unwrap();
+        backend.insert(FAN_TEST, UI8, 0_u8).unwrap();
+        backend
+    }
+
+    #[test]
+    fn fans_without_descriptor() {
+        let smc = SMC::with_backend(apple_silicon());
+        let fans = smc.fans().unwrap();
+
+        assert_eq!(fans.len(), 1);
+        assert_eq!(fans[0].name(), "");
+        assert_eq!(fans[0].current_speed().unwrap(), 2317.5);
+        assert_eq!(fans[0].min_speed().unwrap(), 1200.0);
+        assert_eq!(fans[0].max_speed().unwrap(), 4900.0);
+        assert_eq!(fans[0].mode().unwrap(), FanMode::Auto);
+    }
+
+    #[test]
+    fn idle_auto_fan_is_healthy() {
+        let backend = apple_silicon();
+        backend.insert(four_char_code!("F0Ac"), FLT, 0.0).unwrap();
+        backend.insert(four_char_code!("F0Tg"), FLT, 0.0).unwrap();
+        let smc = SMC::with_backend(backend);
+        let fan = smc.fan(0).unwrap();
+
+        assert_eq!(fan.mode().unwrap(), FanMode::Auto);
+        assert_eq!(
+            fan.health_over(1, Duration::from_millis(0)).unwrap(),
+            FanHealth::Ok
+        );
+
+        fan.set_mode(FanMode::Forced).unwrap();
+        assert_eq!(
+            fan.health_over(1, Duration::from_millis(0)).unwrap(),
+            FanHealth::Stalled
+        );
+    }
+
+    #[test]
+    fn mode_without_forced_bitmask() {
+        let smc = SMC::with_backend(apple_silicon());
+        let fan = smc.fan(0).unwrap();
+
+        fan.set_mode(FanMode::Forced).unwrap();
+        assert_eq!(fan.mode().unwrap(), FanMode::Forced);
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 1);
+
+        fan.set_mode(FanMode::Auto).unwrap();
+        assert_eq!(fan.mode().unwrap(), FanMode::Auto);
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 0);
+    }
+
+    #[test]
+    fn auto_keeps_other_fans_unlocked() {
+        let backend = apple_silicon();
+        backend.insert(four_char_code!("FNum"), UI8, 2_u8).unwrap();
+        backend
+            .insert(four_char_code!("F1Ac"), FLT, 2000.0)
+            .unwrap();
+        backend.insert(four_char_code!("F1Md"), UI8, 0_u8).unwrap();
+        let smc = SMC::with_backend(backend);
+        let (fan0, fan1) = (smc.fan(0).unwrap(), smc.fan(1).unwrap());
+
+        fan0.set_mode(FanMode::Forced).unwrap();
+        fan1.set_mode(FanMode::Forced).unwrap();
+
+        fan0.set_mode(FanMode::Auto).unwrap();
+        assert_eq!(fan0.mode().unwrap(), FanMode::Auto);
+        assert_eq!(fan1.mode().unwrap(), FanMode::Forced);
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 1);
+
+        fan1.set_mode(FanMode::Auto).unwrap();
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 0);
+    }
+
+    #[test]
+    fn rejected_force_speed_leaves_fan_alone() {
+        let smc = SMC::with_backend(apple_silicon());
+        let fan = smc.fan(0).unwrap();
+        smc.write_key(FAN_TEST, 1_u8).unwrap();
+
+        match fan.force_speed(9000.0) {
+            Err(SMCError::UnsafeFanSpeed) => (),
+            res => panic!("unexpected {:?}", res.map(|_| ())),
+        }
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 1);
+        assert_eq!(fan.mode().unwrap(), FanMode::Auto);
+
+        let guard = fan.force_speed(3000.0).unwrap();
+        assert_eq!(fan.mode().unwrap(), FanMode::Forced);
+        drop(guard);
+        assert_eq!(fan.mode().unwrap(), FanMode::Auto);
+        assert_eq!(smc.read_key::<u8>(FAN_TEST).unwrap(), 0);
+    }
+
+    #[test]
+    fn mode_from_forced_bitmask() {
+        let backend = MemoryBackend::with_platform(Platform::Intel);
+        backend.insert(four_char_code!("F1Ac"), FLT, 0.0).unwrap();
+        backend
+            .insert(
+                FORCED_FANS,
+                DataType {
+                    id: four_char_code!("ui16"),
+                    size: 2,
+                },
+                0_u16,
+            )
+            .unwrap();
+        let smc = SMC::with_backend(backend);
+        let fan = smc.fan(1).unwrap();
+
+        fan.set_mode(FanMode::Forced).unwrap();
+        assert_eq!(smc.read_key::<u16>(FORCED_FANS).unwrap(), 0b10);
+        assert_eq!(fan.mode().unwrap(), FanMode::Forced);
+    }
+
+    #[test]
+    fn apply_keeps_concurrent_changes() {
+        let backend = MemoryBackend::with_platform(Platform::Intel);
+        backend.insert(four_char_code!("F0Ac"), FLT, 0.0).unwrap();
+        backend.insert(four_char_code!("F1Ac"), FLT, 0.0).unwrap();
+        backend
+            .insert(
+                FORCED_FANS,
+                DataType {
+                    id: four_char_code!("ui16"),
+                    size: 2,
+                },
+                0_u16,
+            )
+            .unwrap();
+        let smc = SMC::with_backend(backend);
+
+        let mut snapshot = smc.managed_fans().unwrap();
+        smc.fan(1).unwrap().set_mode(FanMode::Forced).unwrap();
+        snapshot.set_managed(0, false);
+        snapshot.apply(&smc).unwrap();
+        assert_eq!(smc.read_key::<u16>(FORCED_FANS).unwrap(), 0b11);
+
+        ManagedFans::all().apply(&smc).unwrap();
+        assert_eq!(smc.read_key::<u16>(FORCED_FANS).unwrap(), 0);
+    }
+
+    #[test]
+    fn missing_fan() {
+        let smc = SMC::with_backend(apple_silicon());
+        match smc.fan(1) {
+            Err(err) => match err.kind() {
+                SMCError::KeyNotFound(key) => assert_eq!(*key, four_char_code!("F1Ac")),
+                err => panic!("unexpected {:?}", err),
+            },
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn thermal_sources_by_zone() {
+        let backend = || {
+            let backend = apple_silicon();
+            for key in &["Tp0a", "Tp01", "Tp09", "TG0P", "Tg05", "TA0P"] {
+                backend.insert(FourCharCode::from(*key), FLT, 40.0).unwrap();
+            }
+            backend
+        };
+        let keys = |smc: SMC| -> Vec<String> {
+            smc.fan_thermal_sources(0)
+                .unwrap()
+                .iter()
+                .map(|s| s.key().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            keys(SMC::with_backend(backend())),
+            vec!["Tp09", "Tp01", "Tp0a"]
+        );
+
+        let gpu = backend();
+        let mut desc = SMCBytes::default();
+        desc.0[1] = 1;
+        gpu.insert_raw(
+            four_char_code!("F0ID"),
+            KeyInfo {
+                data_type: DataType {
+                    id: four_char_code!("{fds"),
+                    size: 16,
+                },
+                attributes: KeyAttributes::READ,
+            },
+            desc,
+        );
+        assert_eq!(keys(SMC::with_backend(gpu)), vec!["TG0P", "Tg05"]);
+    }
+}
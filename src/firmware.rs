@@ -0,0 +1,73 @@
+use std::fmt;
+
+use crate::{keys::consts::FIRMWARE_REVISION, SMCError, SMC};
+
+use four_char_code::{four_char_code, FourCharCode};
+
+const KEY_BOOT_REVISION: FourCharCode = four_char_code!("RVBF");
+const KEY_BRANCH: FourCharCode = four_char_code!("RBr ");
+const KEY_MODE: FourCharCode = four_char_code!("RMde");
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Revision {
+    pub major: u8,
+    pub minor: u8,
+    pub build: u8,
+    pub reserved: u8,
+    pub release: u16,
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}{:x}{}",
+            self.major, self.minor, self.build, self.release
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FirmwareVersion {
+    pub revision: Option<Revision>,
+    pub boot_revision: Option<Revision>,
+    pub branch: Option<String>,
+    pub mode: Option<char>,
+}
+
+impl SMC {
+    fn read_optional<T, F>(&self, key: FourCharCode, f: F) -> Result<Option<T>, SMCError>
+    where
+        F: FnOnce(&SMC, FourCharCode) -> Result<T, SMCError>,
+    {
+        match f(self, key) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn firmware_version(&self) -> Result<FirmwareVersion, SMCError> {
+        let revision = self.read_optional(FIRMWARE_REVISION, |smc, key| smc.read_key(key))?;
+        let boot_revision = self.read_optional(KEY_BOOT_REVISION, |smc, key| smc.read_key(key))?;
+        let branch = self.read_optional(KEY_BRANCH, |smc, key| {
+            let (_, val) = smc.read_key_raw(key)?;
+            Ok(val.as_string())
+        })?;
+        let mode = self.read_optional(KEY_MODE, |smc, key| {
+            let (_, val) = smc.read_key_raw(key)?;
+            Ok(val
+                .data()
+                .first()
+                .filter(|c| c.is_ascii_graphic())
+                .map(|c| *c as char))
+        })?;
+
+        Ok(FirmwareVersion {
+            revision,
+            boot_revision,
+            branch: branch.and_then(|b| b),
+            mode: mode.and_then(|m| m),
+        })
+    }
+}
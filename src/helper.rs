@@ -0,0 +1,464 @@
+use std::ffi::CString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::{
+    DataType, KeyAttributes, KeyInfo, SMCBackend, SMCBytes, SMCError, SMCKey, SMCRepr, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+const OP_KEY_INFO: u8 = 1;
+const OP_READ: u8 = 2;
+const OP_WRITE: u8 = 3;
+const OP_KEY_AT_INDEX: u8 = 4;
+
+const STATUS_OK: u8 = 0;
+const STATUS_KEY_NOT_FOUND: u8 = 1;
+const STATUS_NOT_READABLE: u8 = 2;
+const STATUS_NOT_WRITABLE: u8 = 3;
+const STATUS_NOT_PRIVILEGED: u8 = 4;
+const STATUS_WRITE_DENIED: u8 = 5;
+const STATUS_KEY_INDEX_RANGE: u8 = 6;
+const STATUS_UNSUPPORTED_OP: u8 = 7;
+const STATUS_KEY_SIZE_MISMATCH: u8 = 8;
+const STATUS_OTHER: u8 = 0xff;
+
+struct Request {
+    op: u8,
+    arg: u32,
+    data: Vec<u8>,
+}
+
+struct Response {
+    status: u8,
+    io_res: i32,
+    smc_res: u8,
+    data: Vec<u8>,
+}
+
+fn write_frame<W: Write>(w: &mut W, head: &[u8], data: &[u8]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(head.len() + 1 + data.len());
+    buf.extend_from_slice(head);
+    buf.push(data.len() as u8);
+    buf.extend_from_slice(data);
+    w.write_all(&buf)?;
+    w.flush()
+}
+
+fn read_data<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let mut len = [0_u8; 1];
+    r.read_exact(&mut len)?;
+    let mut data = vec![0_u8; usize::from(len[0])];
+    r.read_exact(&mut data)?;
+    Ok(data)
+}
+
+impl Request {
+    fn send<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let arg = self.arg.to_be_bytes();
+        write_frame(w, &[self.op, arg[0], arg[1], arg[2], arg[3]], &self.data)
+    }
+
+    fn recv<R: Read>(r: &mut R) -> io::Result<Request> {
+        let mut head = [0_u8; 5];
+        r.read_exact(&mut head)?;
+        Ok(Request {
+            op: head[0],
+            arg: u32::from_be_bytes([head[1], head[2], head[3], head[4]]),
+            data: read_data(r)?,
+        })
+    }
+}
+
+impl Response {
+    fn ok(data: Vec<u8>) -> Response {
+        Response {
+            status: STATUS_OK,
+            io_res: 0,
+            smc_res: 0,
+            data,
+        }
+    }
+
+    fn error(err: &SMCError) -> Response {
+        let status = match err.root() {
+            SMCError::KeyNotFound(_) => STATUS_KEY_NOT_FOUND,
+            SMCError::NotReadable(_) => STATUS_NOT_READABLE,
+            SMCError::NotWritable(_) => STATUS_NOT_WRITABLE,
+            SMCError::NotPrivileged => STATUS_NOT_PRIVILEGED,
+            SMCError::WriteDenied(_) => STATUS_WRITE_DENIED,
+            SMCError::KeyIndexRange => STATUS_KEY_INDEX_RANGE,
+            SMCError::KeySizeMismatch(_) => STATUS_KEY_SIZE_MISMATCH,
+            _ => STATUS_OTHER,
+        };
+
+        Response {
+            status,
+            io_res: err.io_result().unwrap_or(0),
+            smc_res: err.smc_result().unwrap_or(0),
+            data: Vec::new(),
+        }
+    }
+
+    fn into_result(self, key: FourCharCode) -> Result<Vec<u8>, SMCError> {
+        match self.status {
+            STATUS_OK => Ok(self.data),
+            STATUS_KEY_NOT_FOUND => Err(SMCError::KeyNotFound(key)),
+            STATUS_NOT_READABLE => Err(SMCError::NotReadable(key)),
+            STATUS_NOT_WRITABLE => Err(SMCError::NotWritable(key)),
+            STATUS_NOT_PRIVILEGED => Err(SMCError::NotPrivileged),
+            STATUS_WRITE_DENIED => Err(SMCError::WriteDenied(key)),
+            STATUS_KEY_INDEX_RANGE => Err(SMCError::KeyIndexRange),
+            STATUS_UNSUPPORTED_OP => Err(SMCError::UnsupportedFeature),
+            STATUS_KEY_SIZE_MISMATCH => Err(SMCError::KeySizeMismatch(key)),
+            _ => Err(SMCError::Unknown(self.io_res, self.smc_res)),
+        }
+    }
+
+    fn send<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let io_res = self.io_res.to_be_bytes();
+        write_frame(
+            w,
+            &[
+                self.status,
+                io_res[0],
+                io_res[1],
+                io_res[2],
+                io_res[3],
+                self.smc_res,
+            ],
+            &self.data,
+        )
+    }
+
+    fn recv<R: Read>(r: &mut R) -> io::Result<Response> {
+        let mut head = [0_u8; 6];
+        r.read_exact(&mut head)?;
+        Ok(Response {
+            status: head[0],
+            io_res: i32::from_be_bytes([head[1], head[2], head[3], head[4]]),
+            smc_res: head[5],
+            data: read_data(r)?,
+        })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_ids(stream: &UnixStream) -> io::Result<(libc::uid_t, libc::gid_t)> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if res == 0 {
+        Ok((cred.uid, cred.gid))
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_ids(stream: &UnixStream) -> io::Result<(libc::uid_t, libc::gid_t)> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } == 0 {
+        Ok((uid, gid))
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Access {
+    uids: Vec<libc::uid_t>,
+    gids: Vec<libc::gid_t>,
+}
+
+impl Access {
+    fn allows(&self, stream: &UnixStream) -> bool {
+        let (uid, gid) = match peer_ids(stream) {
+            Ok(ids) => ids,
+            Err(_) => return false,
+        };
+
+        uid == 0
+            || uid == unsafe { libc::geteuid() }
+            || self.uids.contains(&uid)
+            || self.gids.contains(&gid)
+    }
+}
+
+// The socket is created owner-only; clients other than root and the server's
+// own user must be allowed explicitly with `allow_uid`/`allow_gid`, and the
+// socket opened up for them with `set_mode`/`set_group`.
+pub struct HelperServer {
+    smc: SMC,
+    listener: UnixListener,
+    path: PathBuf,
+    access: Access,
+}
+
+impl HelperServer {
+    pub fn bind<P: AsRef<Path>>(path: P, smc: SMC) -> io::Result<HelperServer> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        let server = HelperServer {
+            smc,
+            listener,
+            path,
+            access: Access::default(),
+        };
+        server.set_mode(0o600)?;
+        Ok(server)
+    }
+
+    pub fn set_mode(&self, mode: u32) -> io::Result<()> {
+        fs::set_permissions(&self.path, fs::Permissions::from_mode(mode))
+    }
+
+    pub fn set_group(&self, gid: libc::gid_t) -> io::Result<()> {
+        let path = CString::new(self.path.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let res = unsafe { libc::chown(path.as_ptr(), libc::uid_t::max_value(), gid) };
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    pub fn allow_uid(mut self, uid: libc::uid_t) -> HelperServer {
+        self.access.uids.push(uid);
+        self
+    }
+
+    pub fn allow_gid(mut self, gid: libc::gid_t) -> HelperServer {
+        self.access.gids.push(gid);
+        self
+    }
+
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            if !self.access.allows(&stream) {
+                continue;
+            }
+
+            let smc = self.smc.clone();
+            thread::spawn(move || {
+                let _ = HelperServer::handle(&smc.0, stream);
+            });
+        }
+
+        Ok(())
+    }
+
+    fn handle(smc_repr: &SMCRepr, mut stream: UnixStream) -> io::Result<()> {
+        loop {
+            let request = match Request::recv(&mut stream) {
+                Ok(request) => request,
+                Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            let response = match HelperServer::dispatch(smc_repr, &request) {
+                Ok(data) => Response::ok(data),
+                Err(None) => Response {
+                    status: STATUS_UNSUPPORTED_OP,
+                    io_res: 0,
+                    smc_res: 0,
+                    data: Vec::new(),
+                },
+                Err(Some(err)) => Response::error(&err),
+            };
+            response.send(&mut stream)?;
+        }
+    }
+
+    fn dispatch(smc_repr: &SMCRepr, request: &Request) -> Result<Vec<u8>, Option<SMCError>> {
+        let key = FourCharCode(request.arg);
+        match request.op {
+            OP_KEY_INFO => {
+                let info = smc_repr.key_info(key)?;
+                let mut data = Vec::with_capacity(9);
+                data.extend_from_slice(&info.data_type.id.to_u32().to_be_bytes());
+                data.extend_from_slice(&info.data_type.size.to_be_bytes());
+                data.push(info.attributes.bits());
+                Ok(data)
+            }
+            OP_READ => {
+                let info = smc_repr.key_information(key)?;
+                let bytes = smc_repr.read_bytes(SMCKey { code: key, info })?;
+                let len = std::cmp::min(info.size as usize, bytes.0.len());
+                Ok(bytes.0[..len].to_vec())
+            }
+            OP_WRITE => {
+                let info = smc_repr.key_information(key)?;
+                let len = request.data.len();
+                if len != info.size as usize || len > SMCBytes::default().0.len() {
+                    return Err(Some(SMCError::KeySizeMismatch(key)));
+                }
+                let mut bytes = SMCBytes::default();
+                bytes.0[..len].copy_from_slice(&request.data);
+                smc_repr.write_bytes(SMCKey { code: key, info }, bytes)?;
+                Ok(Vec::new())
+            }
+            OP_KEY_AT_INDEX => {
+                let key = smc_repr.key_information_at_index(request.arg)?;
+                Ok(key.to_u32().to_be_bytes().to_vec())
+            }
+            _ => Err(None),
+        }
+    }
+}
+
+pub struct RemoteSMC {
+    stream: Mutex<UnixStream>,
+}
+
+impl RemoteSMC {
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<RemoteSMC, SMCError> {
+        let stream = UnixStream::connect(path).map_err(|_| SMCError::FailedToOpen)?;
+        Ok(RemoteSMC {
+            stream: Mutex::new(stream),
+        })
+    }
+
+    fn call(&self, op: u8, arg: u32, data: &[u8]) -> Result<Response, SMCError> {
+        let mut stream = self.stream.lock().unwrap();
+        let request = Request {
+            op,
+            arg,
+            data: data.to_vec(),
+        };
+        request
+            .send(&mut *stream)
+            .and_then(|_| Response::recv(&mut *stream))
+            .map_err(|_| SMCError::DeviceAccess)
+    }
+}
+
+impl SMCBackend for RemoteSMC {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        let data = self
+            .call(OP_KEY_INFO, key.to_u32(), &[])?
+            .into_result(key)?;
+        if data.len() < 9 {
+            return Err(SMCError::SpuriousData);
+        }
+
+        Ok(KeyInfo {
+            data_type: DataType {
+                id: FourCharCode(u32::from_be_bytes([data[0], data[1], data[2], data[3]])),
+                size: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            },
+            attributes: KeyAttributes(data[8]),
+        })
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        let data = self
+            .call(OP_READ, key.code.to_u32(), &[])?
+            .into_result(key.code)?;
+        let mut bytes = SMCBytes::default();
+        let len = std::cmp::min(data.len(), bytes.0.len());
+        bytes.0[..len].copy_from_slice(&data[..len]);
+        Ok(bytes)
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        let len = std::cmp::min(key.info.size as usize, bytes.0.len());
+        self.call(OP_WRITE, key.code.to_u32(), &bytes.0[..len])?
+            .into_result(key.code)
+            .map(|_| ())
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        let data = self
+            .call(OP_KEY_AT_INDEX, index, &[])?
+            .into_result(FourCharCode(0))?;
+        if data.len() < 4 {
+            return Err(SMCError::SpuriousData);
+        }
+
+        Ok(FourCharCode(u32::from_be_bytes([
+            data[0], data[1], data[2], data[3],
+        ])))
+    }
+}
+
+impl SMC {
+    pub fn connect_helper<P: AsRef<Path>>(path: P) -> Result<SMC, SMCError> {
+        Ok(SMC::with_backend(RemoteSMC::connect(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+    use four_char_code::four_char_code;
+
+    const UI16: DataType = DataType {
+        id: four_char_code!("ui16"),
+        size: 2,
+    };
+
+    #[test]
+    fn owner_only_socket_and_exact_writes() {
+        let path = std::env::temp_dir().join(format!("smc-helper-{}.sock", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let backend = MemoryBackend::new();
+        backend
+            .insert(four_char_code!("F0Tg"), UI16, 1200_u16)
+            .unwrap();
+        let server = HelperServer::bind(&path, SMC::with_backend(backend)).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        thread::spawn(move || server.serve());
+
+        let remote = RemoteSMC::connect(&path).unwrap();
+        let key = four_char_code!("F0Tg").to_u32();
+        let data = remote
+            .call(OP_READ, key, &[])
+            .unwrap()
+            .into_result(FourCharCode(key))
+            .unwrap();
+        assert_eq!(data, vec![0x04, 0xb0]);
+        match remote
+            .call(OP_WRITE, key, &[0x01])
+            .unwrap()
+            .into_result(FourCharCode(key))
+        {
+            Err(SMCError::KeySizeMismatch(_)) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+        assert!(remote
+            .call(OP_WRITE, key, &[0x05, 0xdc])
+            .unwrap()
+            .into_result(FourCharCode(key))
+            .is_ok());
+
+        let _ = fs::remove_file(&path);
+    }
+}
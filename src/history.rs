@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::{SMCError, SMCVal, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone)]
+pub struct SensorReading {
+    pub key: FourCharCode,
+    pub value: SMCVal,
+    pub timestamp: Instant,
+}
+
+impl SensorReading {
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value.as_f64()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    capacity: usize,
+    readings: VecDeque<SensorReading>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            capacity,
+            readings: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    pub fn push(&mut self, reading: SensorReading) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+    }
+
+    pub fn clear(&mut self) {
+        self.readings.clear();
+    }
+
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, SensorReading> {
+        self.readings.iter()
+    }
+
+    pub fn latest(&self) -> Option<&SensorReading> {
+        self.readings.back()
+    }
+
+    fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.readings.iter().filter_map(SensorReading::as_f64)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.values().fold(None, |acc, v| match acc {
+            Some(min) if min <= v => Some(min),
+            _ => Some(v),
+        })
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values().fold(None, |acc, v| match acc {
+            Some(max) if max >= v => Some(max),
+            _ => Some(v),
+        })
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        let (sum, count) = self
+            .values()
+            .fold((0.0, 0_usize), |(sum, count), v| (sum + v, count + 1));
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a History {
+    type Item = &'a SensorReading;
+    type IntoIter = std::collections::vec_deque::Iter<'a, SensorReading>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.readings.iter()
+    }
+}
+
+impl SMC {
+    pub fn read_sensor(&self, key: FourCharCode) -> Result<SensorReading, SMCError> {
+        let (_, value) = self.read_key_raw(key)?;
+        Ok(SensorReading {
+            key,
+            value,
+            timestamp: Instant::now(),
+        })
+    }
+}
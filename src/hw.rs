@@ -0,0 +1,75 @@
+use std::os::raw::{c_char, c_void};
+
+use crate::SMCError;
+
+use libc::{sysctl, CTL_HW};
+
+const HW_PACKAGES: i32 = 125;
+const HW_PHYSICALCPU: i32 = 101;
+
+fn sysctl_u32(name: i32) -> Result<u32, SMCError> {
+    let mut mib: [i32; 2] = [CTL_HW, name];
+    let mut num: u32 = 0;
+    let mut len: usize = std::mem::size_of::<u32>();
+
+    let res = unsafe {
+        sysctl(
+            &mut mib[0] as *mut _,
+            2,
+            &mut num as *mut _ as *mut c_void,
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if res == -1 {
+        Err(sysctl_errno!())
+    } else {
+        Ok(num)
+    }
+}
+
+pub(crate) fn sysctl_by_name(
+    name: &[u8],
+    buf: *mut c_void,
+    len: &mut usize,
+) -> Result<(), SMCError> {
+    let res = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr() as *const c_char,
+            buf,
+            len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    if res == -1 {
+        Err(sysctl_errno!())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn cpu_packages() -> Result<usize, SMCError> {
+    sysctl_u32(HW_PACKAGES).map(|num| num as usize)
+}
+
+pub fn physical_cpus() -> Result<usize, SMCError> {
+    sysctl_u32(HW_PHYSICALCPU).map(|num| num as usize)
+}
+
+pub fn model_identifier() -> Result<String, SMCError> {
+    let mut len: usize = 0;
+    sysctl_by_name(b"hw.model\0", std::ptr::null_mut(), &mut len)?;
+
+    let mut buf: Vec<u8> = vec![0; len];
+    sysctl_by_name(b"hw.model\0", buf.as_mut_ptr() as *mut c_void, &mut len)?;
+
+    buf.truncate(len);
+    while buf.last() == Some(&0) {
+        buf.pop();
+    }
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
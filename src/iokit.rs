@@ -0,0 +1,405 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use crate::{
+    sys::*, DataType, KeyAttributes, KeyInfo, Platform, PowerLimits, RetryPolicy, SMCBackend,
+    SMCBytes, SMCError, SMCKey,
+};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone)]
+#[repr(u8)]
+enum SMCSelector {
+    Unknown = 0,
+    // HandleYPCEvent = 2, is driven by the kernel; notifications reach user
+    // space through IOGeneralInterest instead (see events.rs).
+    ReadKey = 5,
+    WriteKey = 6,
+    GetKeyFromIndex = 8,
+    GetKeyInfo = 9,
+    ReadPLimit = 11,
+}
+
+impl Default for SMCSelector {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+#[repr(C)]
+struct SMCVersion {
+    major: u8,
+    minor: u8,
+    build: u8,
+    reserved: u8,
+    release: u16,
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+#[repr(C)]
+struct SMCPLimitData {
+    version: u16,
+    length: u16,
+    cpu_plimit: u32,
+    gpu_plimit: u32,
+    mem_plimit: u32,
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+#[repr(C)]
+struct SMCKeyInfoData {
+    data_size: u32,
+    data_type: FourCharCode,
+    data_attributes: u8,
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+#[repr(C)]
+struct SMCParam {
+    key: FourCharCode,
+    vers: SMCVersion,
+    p_limit_data: SMCPLimitData,
+    key_info: SMCKeyInfoData,
+    result: u8,
+    status: u8,
+    selector: SMCSelector,
+    data32: u32,
+    bytes: SMCBytes,
+}
+
+fn is_disconnected(result: kern_return_t) -> bool {
+    result == kIOReturnNotOpen
+        || result == kIOReturnNoDevice
+        || result == kIOReturnNotAttached
+        || result == MACH_SEND_INVALID_DEST
+}
+
+pub(crate) const DEFAULT_SERVICE: &str = "AppleSMC";
+
+pub(crate) fn matching_service(service: &CStr) -> Result<io_service_t, SMCError> {
+    let device = unsafe {
+        IOServiceGetMatchingService(
+            kIOMainPortDefault,
+            IOServiceMatching(service.as_ptr() as *const _),
+        )
+    };
+
+    if device.is_null() {
+        Err(SMCError::DriverNotFound)
+    } else {
+        Ok(device)
+    }
+}
+
+pub(crate) fn nth_matching_service(service: &CStr, index: usize) -> Result<io_service_t, SMCError> {
+    if index == 0 {
+        return matching_service(service);
+    }
+
+    let mut device = None;
+    for (i, other) in matching_services(service)?.into_iter().enumerate() {
+        if i == index {
+            device = Some(other);
+        } else {
+            unsafe { IOObjectRelease(other) };
+        }
+    }
+    device.ok_or(SMCError::DriverNotFound)
+}
+
+pub(crate) fn matching_services(service: &CStr) -> Result<Vec<io_service_t>, SMCError> {
+    let mut iter: io_iterator_t = MACH_PORT_NULL;
+    let result = unsafe {
+        IOServiceGetMatchingServices(
+            kIOMainPortDefault,
+            IOServiceMatching(service.as_ptr() as *const _),
+            &mut iter,
+        )
+    };
+    if result != kIOReturnSuccess {
+        return Err(SMCError::DriverNotFound);
+    }
+
+    let mut res = Vec::new();
+    loop {
+        let device = unsafe { IOIteratorNext(iter) };
+        if device.is_null() {
+            break;
+        }
+        res.push(device);
+    }
+    unsafe { IOObjectRelease(iter) };
+
+    Ok(res)
+}
+
+pub struct IOKitBackend {
+    conn: Mutex<io_connect_t>,
+    service: CString,
+    index: usize,
+    open_type: u32,
+    platform: Option<Platform>,
+    reconnect: bool,
+    retry: RetryPolicy,
+    detached: AtomicBool,
+}
+
+impl IOKitBackend {
+    pub fn new() -> Result<IOKitBackend, SMCError> {
+        IOKitBackend::with_service(DEFAULT_SERVICE, 0)
+    }
+
+    pub fn with_service(service: &str, open_type: u32) -> Result<IOKitBackend, SMCError> {
+        IOKitBackend::with_service_index(service, 0, open_type)
+    }
+
+    pub fn with_service_index(
+        service: &str,
+        index: usize,
+        open_type: u32,
+    ) -> Result<IOKitBackend, SMCError> {
+        let service = CString::new(service).map_err(|_| SMCError::DriverNotFound)?;
+        Ok(IOKitBackend {
+            conn: Mutex::new(IOKitBackend::open(&service, index, open_type)?),
+            service,
+            index,
+            open_type,
+            platform: Platform::detect().ok(),
+            reconnect: false,
+            retry: RetryPolicy::none(),
+            detached: AtomicBool::new(false),
+        })
+    }
+
+    /// # Safety
+    ///
+    /// `conn` must be a valid, open connection to the AppleSMC service. Ownership is
+    /// transferred: the connection is closed when the returned value is dropped.
+    pub unsafe fn from_raw(conn: io_connect_t) -> IOKitBackend {
+        IOKitBackend {
+            conn: Mutex::new(conn),
+            service: CString::new(DEFAULT_SERVICE).unwrap(),
+            index: 0,
+            open_type: 0,
+            platform: Platform::detect().ok(),
+            reconnect: false,
+            retry: RetryPolicy::none(),
+            detached: AtomicBool::new(false),
+        }
+    }
+
+    pub fn service_count(service: &str) -> Result<usize, SMCError> {
+        let service = CString::new(service).map_err(|_| SMCError::DriverNotFound)?;
+        let services = matching_services(&service)?;
+        for device in services.iter() {
+            unsafe { IOObjectRelease(*device) };
+        }
+        Ok(services.len())
+    }
+
+    pub fn as_raw(&self) -> io_connect_t {
+        *self.conn.lock().unwrap()
+    }
+
+    pub fn into_raw(self) -> io_connect_t {
+        let conn = self.as_raw();
+        std::mem::forget(self);
+        conn
+    }
+
+    pub fn with_reconnect(mut self, reconnect: bool) -> IOKitBackend {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> IOKitBackend {
+        self.retry = retry;
+        self
+    }
+
+    fn open(service: &CStr, index: usize, open_type: u32) -> Result<io_connect_t, SMCError> {
+        let conn: io_connect_t = MACH_PORT_NULL;
+        let result: kern_return_t;
+        let device = nth_matching_service(service, index)?;
+
+        result = unsafe { IOServiceOpen(&mut *device, mach_task_self(), open_type, &conn) };
+        unsafe { IOObjectRelease(&mut *device) };
+        if result != kIOReturnSuccess {
+            return Err(SMCError::FailedToOpen);
+        }
+
+        Ok(conn)
+    }
+
+    fn call_driver(&self, input: &SMCParam) -> Result<SMCParam, SMCError> {
+        self.retry.run(|| self.call_driver_once(input))
+    }
+
+    #[allow(non_upper_case_globals)]
+    fn call_driver_once(&self, input: &SMCParam) -> Result<SMCParam, SMCError> {
+        let mut output: SMCParam = Default::default();
+        let input_size: usize = std::mem::size_of::<SMCParam>();
+        let mut output_size: usize = std::mem::size_of::<SMCParam>();
+
+        let mut conn = self.conn.lock().unwrap();
+
+        let call = |conn: io_connect_t, output: &mut SMCParam, output_size: &mut usize| unsafe {
+            IOConnectCallStructMethod(
+                conn,
+                2,
+                input as *const _ as *const c_void,
+                input_size,
+                output as *mut _ as *mut c_void,
+                output_size,
+            )
+        };
+
+        let mut result = call(*conn, &mut output, &mut output_size);
+        if self.reconnect && is_disconnected(result) {
+            let new_conn = IOKitBackend::open(&self.service, self.index, self.open_type)?;
+            unsafe { IOServiceClose(*conn) };
+            *conn = new_conn;
+            output = Default::default();
+            output_size = std::mem::size_of::<SMCParam>();
+            result = call(*conn, &mut output, &mut output_size);
+        }
+
+        match (result, output.result) {
+            (kIOReturnSuccess, 0) => Ok(output),
+            (kIOReturnSuccess, smc_res) => {
+                Err(SMCError::from_smc_result(input.key, result, smc_res))
+            }
+            (kIOReturnNotPrivileged, _) => Err(SMCError::NotPrivileged),
+            (kIOReturnNotOpen, _) => Err(SMCError::NotOpen),
+            (kIOReturnBusy, _) => Err(SMCError::Busy),
+            (kIOReturnBadArgument, _) => Err(SMCError::InvalidArgument),
+            (kIOReturnUnsupported, _) => Err(SMCError::Unsupported),
+            _ => Err(SMCError::Unknown(result, output.result)),
+        }
+    }
+}
+
+impl SMCBackend for IOKitBackend {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        let mut input: SMCParam = Default::default();
+        input.key = key;
+        input.selector = SMCSelector::GetKeyInfo;
+
+        let output = self.call_driver(&input)?;
+
+        Ok(KeyInfo {
+            data_type: DataType {
+                id: output.key_info.data_type,
+                size: output.key_info.data_size,
+            },
+            attributes: KeyAttributes(output.key_info.data_attributes),
+        })
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        let mut input: SMCParam = Default::default();
+        input.key = key.code;
+        input.key_info.data_size = key.info.size;
+        input.selector = SMCSelector::ReadKey;
+
+        let output = self.call_driver(&input)?;
+
+        Ok(output.bytes)
+    }
+
+    fn read_many(&self, keys: &[SMCKey]) -> Vec<Result<SMCBytes, SMCError>> {
+        let mut input = SMCParam {
+            selector: SMCSelector::ReadKey,
+            ..Default::default()
+        };
+
+        keys.iter()
+            .map(|key| {
+                input.key = key.code;
+                input.key_info.data_size = key.info.size;
+                self.call_driver(&input).map(|output| output.bytes)
+            })
+            .collect()
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        let mut input: SMCParam = Default::default();
+        input.key = key.code;
+        input.bytes = bytes;
+        input.key_info.data_size = key.info.size;
+        input.selector = SMCSelector::WriteKey;
+
+        self.call_driver(&input)?;
+
+        Ok(())
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        let mut input: SMCParam = Default::default();
+        input.selector = SMCSelector::GetKeyFromIndex;
+        input.data32 = index;
+
+        let output = self.call_driver(&input)?;
+
+        Ok(output.key)
+    }
+
+    fn can_write(&self) -> Result<(), SMCError> {
+        if unsafe { libc::geteuid() } == 0 {
+            Ok(())
+        } else {
+            Err(SMCError::NotPrivileged)
+        }
+    }
+
+    fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        let input = SMCParam {
+            selector: SMCSelector::ReadPLimit,
+            ..Default::default()
+        };
+
+        let output = self.call_driver(&input)?;
+
+        Ok(PowerLimits {
+            version: output.p_limit_data.version,
+            cpu: output.p_limit_data.cpu_plimit,
+            gpu: output.p_limit_data.gpu_plimit,
+            memory: output.p_limit_data.mem_plimit,
+        })
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+
+    fn as_raw(&self) -> Option<io_connect_t> {
+        Some(IOKitBackend::as_raw(self))
+    }
+
+    fn detach(&self) -> Option<io_connect_t> {
+        self.detached.store(true, Ordering::SeqCst);
+        Some(*self.conn.lock().unwrap())
+    }
+
+    fn service(&self) -> Option<(&CStr, usize)> {
+        Some((&self.service, self.index))
+    }
+}
+
+impl Drop for IOKitBackend {
+    fn drop(&mut self) {
+        if !self.detached.load(Ordering::SeqCst) {
+            let conn = self.conn.lock().unwrap();
+            unsafe { IOServiceClose(*conn) };
+        }
+    }
+}
+
+unsafe impl Send for IOKitBackend {}
+unsafe impl Sync for IOKitBackend {}
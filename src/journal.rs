@@ -0,0 +1,129 @@
+use std::time::SystemTime;
+
+use crate::{SMCError, SMCKey, SMCVal, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone)]
+pub struct WriteRecord {
+    pub key: FourCharCode,
+    pub old: Option<SMCVal>,
+    pub new: SMCVal,
+    pub timestamp: SystemTime,
+}
+
+impl SMC {
+    pub fn set_journal(&self, enabled: bool) {
+        let mut journal = self.0.journal.lock().unwrap();
+        match (enabled, journal.is_some()) {
+            (true, false) => *journal = Some(Vec::new()),
+            (false, true) => *journal = None,
+            _ => (),
+        }
+    }
+
+    pub fn is_journal_enabled(&self) -> bool {
+        self.0.journal.lock().unwrap().is_some()
+    }
+
+    pub fn write_log(&self) -> Vec<WriteRecord> {
+        match *self.0.journal.lock().unwrap() {
+            Some(ref entries) => entries.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn clear_write_log(&self) {
+        if let Some(ref mut entries) = *self.0.journal.lock().unwrap() {
+            entries.clear();
+        }
+    }
+
+    pub fn undo_all(&self) -> Result<(), SMCError> {
+        // Taking the journal keeps the restoring writes out of it while they
+        // still go through the write policy and rate limiter.
+        let entries = match self.0.journal.lock().unwrap().take() {
+            Some(entries) => entries,
+            None => return Ok(()),
+        };
+
+        let mut res = Ok(());
+        for entry in entries.iter().rev() {
+            if let Some(old) = entry.old {
+                let key = SMCKey {
+                    code: entry.key,
+                    info: old.data_type(),
+                };
+                if let Err(err) = self.0.write_bytes(key, *old.bytes()) {
+                    if res.is_ok() {
+                        res = Err(err);
+                    }
+                }
+            }
+        }
+
+        let mut journal = self.0.journal.lock().unwrap();
+        if journal.is_none() {
+            *journal = Some(Vec::new());
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataType, MemoryBackend, SMCError, WritePolicy, SMC};
+
+    use four_char_code::four_char_code;
+
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+
+    fn smc() -> SMC {
+        let backend = MemoryBackend::new();
+        backend.insert(four_char_code!("F0Md"), UI8, 0_u8).unwrap();
+        backend.insert(four_char_code!("Ftst"), UI8, 0_u8).unwrap();
+        let smc = SMC::with_backend(backend);
+        smc.set_journal(true);
+        smc
+    }
+
+    #[test]
+    fn undo_restores_and_keeps_journal() {
+        let smc = smc();
+        smc.write_key(four_char_code!("Ftst"), 1_u8).unwrap();
+        smc.write_key(four_char_code!("F0Md"), 1_u8).unwrap();
+        smc.write_key(four_char_code!("F0Md"), 2_u8).unwrap();
+        assert_eq!(smc.write_log().len(), 3);
+
+        smc.undo_all().unwrap();
+        assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 0);
+        assert_eq!(smc.read_key::<u8>(four_char_code!("Ftst")).unwrap(), 0);
+        assert!(smc.is_journal_enabled());
+        assert!(smc.write_log().is_empty());
+    }
+
+    #[test]
+    fn undo_honours_write_policy_and_rate_limit() {
+        let smc = smc();
+        smc.write_key(four_char_code!("Ftst"), 1_u8).unwrap();
+        smc.write_key(four_char_code!("F0Md"), 1_u8).unwrap();
+
+        smc.set_write_policy(WritePolicy::deny_all().allow("F0Md"));
+        smc.set_rate_limit(Some(1_000_000));
+        let before = smc.rate_limit_stats().calls;
+
+        match smc.undo_all() {
+            Err(err) => match err.kind() {
+                SMCError::WriteDenied(key) => assert_eq!(*key, four_char_code!("Ftst")),
+                err => panic!("unexpected {:?}", err),
+            },
+            res => panic!("unexpected {:?}", res),
+        }
+        assert_eq!(smc.rate_limit_stats().calls - before, 1);
+        assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 0);
+        assert_eq!(smc.read_key::<u8>(four_char_code!("Ftst")).unwrap(), 1);
+    }
+}
@@ -0,0 +1,175 @@
+use four_char_code::{four_char_code, FourCharCode};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum KeyCategory {
+    Temperature,
+    Fan,
+    Voltage,
+    Current,
+    Power,
+    Battery,
+    Misc,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyDescription {
+    pub key: FourCharCode,
+    pub description: &'static str,
+    pub unit: Option<&'static str>,
+    pub category: KeyCategory,
+}
+
+macro_rules! keys {
+    ( $( $key:literal => $desc:literal, $unit:expr, $cat:ident; )* ) => {
+        &[
+            $(
+                KeyDescription {
+                    key: four_char_code!($key),
+                    description: $desc,
+                    unit: $unit,
+                    category: KeyCategory::$cat,
+                },
+            )*
+        ]
+    };
+}
+
+const CELSIUS: Option<&str> = Some("°C");
+const RPM: Option<&str> = Some("rpm");
+const VOLTS: Option<&str> = Some("V");
+const MILLIVOLTS: Option<&str> = Some("mV");
+const AMPS: Option<&str> = Some("A");
+const MILLIAMPS: Option<&str> = Some("mA");
+const WATTS: Option<&str> = Some("W");
+const PERCENT: Option<&str> = Some("%");
+
+const KEYS: &[KeyDescription] = keys! {
+    "TA0P" => "Ambient", CELSIUS, Temperature;
+    "TA1P" => "Ambient 2", CELSIUS, Temperature;
+    "TB0T" => "Battery", CELSIUS, Temperature;
+    "TB1T" => "Battery 1", CELSIUS, Temperature;
+    "TB2T" => "Battery 2", CELSIUS, Temperature;
+    "TC0C" => "CPU Core 0", CELSIUS, Temperature;
+    "TC0D" => "CPU Die", CELSIUS, Temperature;
+    "TC0E" => "CPU Die (virtual)", CELSIUS, Temperature;
+    "TC0F" => "CPU Die (filtered)", CELSIUS, Temperature;
+    "TC0H" => "CPU Heatsink", CELSIUS, Temperature;
+    "TC0P" => "CPU Proximity", CELSIUS, Temperature;
+    "TCGC" => "PECI GPU", CELSIUS, Temperature;
+    "TCSA" => "PECI SA", CELSIUS, Temperature;
+    "TCXC" => "PECI CPU", CELSIUS, Temperature;
+    "TG0D" => "GPU Die", CELSIUS, Temperature;
+    "TG0H" => "GPU Heatsink", CELSIUS, Temperature;
+    "TG0P" => "GPU Proximity", CELSIUS, Temperature;
+    "TH0P" => "Drive Bay", CELSIUS, Temperature;
+    "TH0a" => "NAND", CELSIUS, Temperature;
+    "TH0b" => "NAND 2", CELSIUS, Temperature;
+    "TH0x" => "NAND Max", CELSIUS, Temperature;
+    "THSP" => "SSD Proximity", CELSIUS, Temperature;
+    "TI0P" => "Thunderbolt", CELSIUS, Temperature;
+    "TL0P" => "LCD Proximity", CELSIUS, Temperature;
+    "TM0P" => "Memory Proximity", CELSIUS, Temperature;
+    "TM0S" => "Memory Slot", CELSIUS, Temperature;
+    "TN0D" => "Northbridge Die", CELSIUS, Temperature;
+    "TN0P" => "Northbridge Proximity", CELSIUS, Temperature;
+    "TO0P" => "Optical Drive", CELSIUS, Temperature;
+    "TPCD" => "Platform Controller Hub Die", CELSIUS, Temperature;
+    "TW0P" => "Airport Proximity", CELSIUS, Temperature;
+    "TaLP" => "Storage Left", CELSIUS, Temperature;
+    "TaRF" => "Storage Right", CELSIUS, Temperature;
+    "Th0H" => "Heatsink", CELSIUS, Temperature;
+    "Th1H" => "Heatsink 2", CELSIUS, Temperature;
+    "Tm0P" => "Mainboard Proximity", CELSIUS, Temperature;
+    "Ts0P" => "Palm Rest", CELSIUS, Temperature;
+    "Ts0S" => "Memory Bank Proximity", CELSIUS, Temperature;
+
+    "FNum" => "Number of fans", None, Fan;
+    "FS! " => "Forced fans bitmask", None, Fan;
+    "Ftst" => "Fan manual control unlock", None, Fan;
+    "F0Ac" => "Fan 0 actual speed", RPM, Fan;
+    "F0Mn" => "Fan 0 minimum speed", RPM, Fan;
+    "F0Mx" => "Fan 0 maximum speed", RPM, Fan;
+    "F0Tg" => "Fan 0 target speed", RPM, Fan;
+    "F0Md" => "Fan 0 mode", None, Fan;
+    "F0ID" => "Fan 0 descriptor", None, Fan;
+    "F1Ac" => "Fan 1 actual speed", RPM, Fan;
+    "F1Mn" => "Fan 1 minimum speed", RPM, Fan;
+    "F1Mx" => "Fan 1 maximum speed", RPM, Fan;
+    "F1Tg" => "Fan 1 target speed", RPM, Fan;
+    "F1Md" => "Fan 1 mode", None, Fan;
+    "F1ID" => "Fan 1 descriptor", None, Fan;
+
+    "VC0C" => "CPU Core", VOLTS, Voltage;
+    "VD0R" => "DC In", VOLTS, Voltage;
+    "VG0C" => "GPU Core", VOLTS, Voltage;
+    "VM0R" => "Memory", VOLTS, Voltage;
+    "VN0C" => "Northbridge", VOLTS, Voltage;
+    "VP0R" => "12V Rail", VOLTS, Voltage;
+
+    "IC0C" => "CPU Core", AMPS, Current;
+    "IC0R" => "CPU Rail", AMPS, Current;
+    "ID0R" => "DC In", AMPS, Current;
+    "IG0C" => "GPU Core", AMPS, Current;
+    "IM0R" => "Memory", AMPS, Current;
+    "IN0C" => "Northbridge", AMPS, Current;
+    "IPBR" => "Battery Rail", AMPS, Current;
+
+    "PC0C" => "CPU Core", WATTS, Power;
+    "PC0R" => "CPU Rail", WATTS, Power;
+    "PCPC" => "CPU Package Cores", WATTS, Power;
+    "PCPG" => "CPU Package GPU", WATTS, Power;
+    "PCPT" => "CPU Package Total", WATTS, Power;
+    "PDTR" => "DC In", WATTS, Power;
+    "PG0R" => "GPU Rail", WATTS, Power;
+    "PGTR" => "GPU Total", WATTS, Power;
+    "PPBR" => "Battery", WATTS, Power;
+    "PSTR" => "System Total", WATTS, Power;
+    "AC-W" => "Adapter wattage", WATTS, Power;
+    "ADPV" => "Adapter voltage", VOLTS, Power;
+    "ADPI" => "Adapter current", AMPS, Power;
+
+    "B0AC" => "Battery 0 current", MILLIAMPS, Battery;
+    "B0AV" => "Battery 0 voltage", MILLIVOLTS, Battery;
+    "BATP" => "Running on battery", None, Battery;
+    "BCLM" => "Battery charge limit", PERCENT, Battery;
+    "BNum" => "Number of batteries", None, Battery;
+    "BSIn" => "Battery status", None, Battery;
+    "BUIC" => "Battery charge", PERCENT, Battery;
+    "CH0B" => "Charge inhibit", None, Battery;
+    "CH0C" => "Charge inhibit", None, Battery;
+
+    "#KEY" => "Number of keys", None, Misc;
+    "KSBL" => "Keyboard backlight", None, Misc;
+    "LKSB" => "Keyboard backlight", None, Misc;
+    "MSDI" => "Optical disk drive full", None, Misc;
+    "MSLD" => "Lid closed", None, Misc;
+    "RBr " => "Firmware branch", None, Misc;
+    "REV " => "Firmware revision", None, Misc;
+    "RMde" => "Firmware mode", None, Misc;
+    "RPlt" => "Platform", None, Misc;
+    "RVBF" => "Boot firmware revision", None, Misc;
+};
+
+pub fn lookup(key: FourCharCode) -> Option<KeyDescription> {
+    KEYS.iter().find(|desc| desc.key == key).cloned()
+}
+
+pub fn all() -> &'static [KeyDescription] {
+    KEYS
+}
+
+pub fn category(key: FourCharCode) -> KeyCategory {
+    if let Some(desc) = lookup(key) {
+        return desc.category;
+    }
+
+    match key.to_u32().to_be_bytes()[0] {
+        b'T' => KeyCategory::Temperature,
+        b'F' => KeyCategory::Fan,
+        b'V' => KeyCategory::Voltage,
+        b'I' => KeyCategory::Current,
+        b'P' => KeyCategory::Power,
+        b'B' => KeyCategory::Battery,
+        _ => KeyCategory::Misc,
+    }
+}
@@ -0,0 +1,288 @@
+use std::{convert::TryFrom, fmt, sync::Arc};
+
+use crate::{
+    keydb::{self, KeyCategory},
+    DataType, SMCError, SMCRepr, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+pub mod consts;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyIndex(u8);
+
+impl KeyIndex {
+    pub const MAX: u8 = 0xf;
+
+    pub fn new(index: u8) -> Option<KeyIndex> {
+        if index <= KeyIndex::MAX {
+            Some(KeyIndex(index))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    pub fn all() -> impl Iterator<Item = KeyIndex> {
+        (0..=KeyIndex::MAX).map(KeyIndex)
+    }
+}
+
+impl fmt::Display for KeyIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:X}", self.0)
+    }
+}
+
+impl From<KeyIndex> for u8 {
+    fn from(index: KeyIndex) -> u8 {
+        index.0
+    }
+}
+
+impl TryFrom<u8> for KeyIndex {
+    type Error = SMCError;
+
+    fn try_from(index: u8) -> Result<KeyIndex, SMCError> {
+        KeyIndex::new(index).ok_or(SMCError::KeyIndexRange)
+    }
+}
+
+impl TryFrom<u32> for KeyIndex {
+    type Error = SMCError;
+
+    fn try_from(index: u32) -> Result<KeyIndex, SMCError> {
+        if index <= u32::from(KeyIndex::MAX) {
+            Ok(KeyIndex(index as u8))
+        } else {
+            Err(SMCError::KeyIndexRange)
+        }
+    }
+}
+
+pub struct Keys {
+    smc_repr: Arc<SMCRepr>,
+    index: u32,
+    len: u32,
+}
+
+impl Iterator for Keys {
+    type Item = Result<FourCharCode, SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        Some(self.smc_repr.key_information_at_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.len - self.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Keys {}
+
+impl Keys {
+    pub fn collect_sorted(self) -> Result<Vec<FourCharCode>, SMCError> {
+        let mut res = self.collect::<Result<Vec<FourCharCode>, SMCError>>()?;
+        res.sort();
+        Ok(res)
+    }
+
+    pub fn in_category(self, category: KeyCategory) -> KeysInCategory {
+        KeysInCategory {
+            keys: self,
+            category,
+        }
+    }
+
+    pub fn matching(self, pattern: &str) -> KeysMatching {
+        KeysMatching {
+            keys: self,
+            pattern: pattern.as_bytes().to_vec(),
+        }
+    }
+}
+
+pub(crate) fn glob_match(pattern: &[u8], key: &[u8]) -> bool {
+    match (pattern.first(), key.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            glob_match(&pattern[1..], key) || (!key.is_empty() && glob_match(pattern, &key[1..]))
+        }
+        (Some(b'?'), Some(_)) => glob_match(&pattern[1..], &key[1..]),
+        (Some(p), Some(k)) if p == k => glob_match(&pattern[1..], &key[1..]),
+        _ => false,
+    }
+}
+
+pub struct KeysMatching {
+    keys: Keys,
+    pattern: Vec<u8>,
+}
+
+impl Iterator for KeysMatching {
+    type Item = Result<FourCharCode, SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.keys.next()? {
+                Ok(key) => {
+                    if glob_match(&self.pattern, &key.to_u32().to_be_bytes()) {
+                        return Some(Ok(key));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.keys.size_hint().1)
+    }
+}
+
+pub struct KeysInCategory {
+    keys: Keys,
+    category: KeyCategory,
+}
+
+impl Iterator for KeysInCategory {
+    type Item = Result<FourCharCode, SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.keys.next()? {
+                Ok(key) => {
+                    if keydb::category(key) == self.category {
+                        return Some(Ok(key));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.keys.size_hint().1)
+    }
+}
+
+pub struct KeysWithInfo {
+    smc_repr: Arc<SMCRepr>,
+    index: u32,
+    len: u32,
+}
+
+impl Iterator for KeysWithInfo {
+    type Item = Result<(FourCharCode, DataType), SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        Some(
+            self.smc_repr
+                .key_information_at_index(index)
+                .and_then(|key| Ok((key, self.smc_repr.key_information(key)?))),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.len - self.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for KeysWithInfo {}
+
+impl SMC {
+    pub fn keys_iter(&self) -> Result<Keys, SMCError> {
+        Ok(Keys {
+            smc_repr: self.0.clone(),
+            index: 0,
+            len: self._keys_len()?,
+        })
+    }
+
+    pub fn all_keys(&self) -> Result<Vec<FourCharCode>, SMCError> {
+        self.keys_iter()?.collect_sorted()
+    }
+
+    pub fn keys_matching(&self, pattern: &str) -> Result<KeysMatching, SMCError> {
+        Ok(self.keys_iter()?.matching(pattern))
+    }
+
+    pub fn keys_in_category(&self, category: KeyCategory) -> Result<KeysInCategory, SMCError> {
+        Ok(self.keys_iter()?.in_category(category))
+    }
+
+    pub fn keys_with_info(&self) -> Result<KeysWithInfo, SMCError> {
+        Ok(KeysWithInfo {
+            smc_repr: self.0.clone(),
+            index: 0,
+            len: self._keys_len()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    fn matches(pattern: &str, key: &str) -> bool {
+        glob_match(pattern.as_bytes(), key.as_bytes())
+    }
+
+    #[test]
+    fn literal() {
+        assert!(matches("TC0P", "TC0P"));
+        assert!(!matches("TC0P", "TC0D"));
+        assert!(matches("FS! ", "FS! "));
+    }
+
+    #[test]
+    fn question_mark() {
+        assert!(matches("TC?P", "TC0P"));
+        assert!(matches("????", "F0Ac"));
+        assert!(!matches("TC?P", "TC0D"));
+        assert!(!matches("???", "TC0P"));
+        assert!(!matches("?????", "TC0P"));
+    }
+
+    #[test]
+    fn star() {
+        assert!(matches("T*", "TC0P"));
+        assert!(matches("F*Ac", "F0Ac"));
+        assert!(matches("*P", "TC0P"));
+        assert!(matches("TC0P*", "TC0P"));
+        assert!(matches("****", "TC0P"));
+        assert!(matches("*", ""));
+        assert!(!matches("F*Ac", "F0Mx"));
+        assert!(!matches("*D", "TC0P"));
+    }
+
+    #[test]
+    fn length_mismatch() {
+        assert!(!matches("TC", "TC0P"));
+        assert!(!matches("TC0P", "TC"));
+        assert!(!matches("TC0PX", "TC0P"));
+        assert!(!matches("", "TC0P"));
+        assert!(matches("TC*", "TC0P"));
+    }
+}
@@ -0,0 +1,56 @@
+use four_char_code::{four_char_code, FourCharCode};
+
+// Temperature
+pub const AMBIENT: FourCharCode = four_char_code!("TA0P");
+pub const BATTERY_TEMPERATURE: FourCharCode = four_char_code!("TB0T");
+pub const CPU_DIE: FourCharCode = four_char_code!("TC0D");
+pub const CPU_HEATSINK: FourCharCode = four_char_code!("TC0H");
+pub const CPU_PROXIMITY: FourCharCode = four_char_code!("TC0P");
+pub const GPU_DIE: FourCharCode = four_char_code!("TG0D");
+pub const GPU_HEATSINK: FourCharCode = four_char_code!("TG0H");
+pub const GPU_PROXIMITY: FourCharCode = four_char_code!("TG0P");
+pub const DRIVE_BAY: FourCharCode = four_char_code!("TH0P");
+pub const MEMORY_PROXIMITY: FourCharCode = four_char_code!("TM0P");
+pub const HEATSINK: FourCharCode = four_char_code!("Th0H");
+pub const PALM_REST: FourCharCode = four_char_code!("Ts0P");
+
+// Fan
+pub const FAN_COUNT: FourCharCode = four_char_code!("FNum");
+pub const FORCED_FANS: FourCharCode = four_char_code!("FS! ");
+pub const FAN_TEST: FourCharCode = four_char_code!("Ftst");
+
+// Voltage
+pub const CPU_CORE_VOLTAGE: FourCharCode = four_char_code!("VC0C");
+pub const DC_IN_VOLTAGE: FourCharCode = four_char_code!("VD0R");
+pub const GPU_CORE_VOLTAGE: FourCharCode = four_char_code!("VG0C");
+
+// Current
+pub const CPU_CORE_CURRENT: FourCharCode = four_char_code!("IC0C");
+pub const DC_IN_CURRENT: FourCharCode = four_char_code!("ID0R");
+pub const GPU_CORE_CURRENT: FourCharCode = four_char_code!("IG0C");
+
+// Power
+pub const CPU_PACKAGE_POWER: FourCharCode = four_char_code!("PCPT");
+pub const CPU_CORE_POWER: FourCharCode = four_char_code!("PC0C");
+pub const GPU_POWER: FourCharCode = four_char_code!("PGTR");
+pub const SYSTEM_POWER: FourCharCode = four_char_code!("PSTR");
+pub const DC_IN_POWER: FourCharCode = four_char_code!("PDTR");
+pub const ADAPTER_WATTS: FourCharCode = four_char_code!("AC-W");
+pub const ADAPTER_VOLTAGE: FourCharCode = four_char_code!("ADPV");
+pub const ADAPTER_CURRENT: FourCharCode = four_char_code!("ADPI");
+
+// Battery
+pub const BATTERY_COUNT: FourCharCode = four_char_code!("BNum");
+pub const BATTERY_INFO: FourCharCode = four_char_code!("BSIn");
+pub const BATTERY_POWERED: FourCharCode = four_char_code!("BATP");
+pub const BATTERY_CHARGE: FourCharCode = four_char_code!("BUIC");
+pub const CHARGE_LIMIT: FourCharCode = four_char_code!("BCLM");
+pub const CHARGE_INHIBIT_B: FourCharCode = four_char_code!("CH0B");
+pub const CHARGE_INHIBIT_C: FourCharCode = four_char_code!("CH0C");
+
+// Misc
+pub const KEY_COUNT: FourCharCode = four_char_code!("#KEY");
+pub const LID_CLOSED: FourCharCode = four_char_code!("MSLD");
+pub const KEYBOARD_BACKLIGHT: FourCharCode = four_char_code!("LKSB");
+pub const FIRMWARE_REVISION: FourCharCode = four_char_code!("REV ");
+pub const PLATFORM: FourCharCode = four_char_code!("RPlt");
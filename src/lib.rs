@@ -1,35 +1,224 @@
-#![cfg(target_os = "macos")]
-
 extern crate four_char_code;
+#[cfg(feature = "stream")]
+extern crate futures_core;
 extern crate libc;
+#[cfg(target_os = "macos")]
 #[macro_use]
 extern crate lazy_static;
 
+macro_rules! fcc_format {
+    ( $fmt:literal, $( $args:expr ),+ ) => {
+        Into::<FourCharCode>::into(format!($fmt, $($args),+))
+    }
+}
+
+#[cfg(target_os = "macos")]
+macro_rules! sysctl_errno {
+    () => {
+        SMCError::Sysctl(::std::io::Error::last_os_error().raw_os_error().unwrap())
+    };
+}
+
+mod alerts;
+mod ambient;
+mod backend;
+mod backlight;
+#[cfg(target_os = "macos")]
+mod builder;
+mod cache;
+#[cfg(feature = "capi")]
+pub mod capi;
 mod conversions;
+mod curve;
+mod dashboard;
+mod decode;
+mod electrical;
+mod energy;
+#[cfg(target_os = "macos")]
+mod events;
+#[cfg(feature = "serde")]
+mod export;
+mod fans;
+mod firmware;
+#[cfg(all(unix, feature = "helper"))]
+mod helper;
+mod history;
+#[cfg(target_os = "macos")]
+pub mod hw;
+#[cfg(target_os = "macos")]
+mod iokit;
+mod journal;
+pub mod keydb;
+pub mod keys;
+mod logger;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod platform;
+mod policy;
+mod power;
+mod ratelimit;
+mod replay;
+mod retry;
+mod sampler;
+mod sensor;
+mod snapshot;
+mod status;
+#[cfg(target_os = "macos")]
 mod sys;
+mod temps;
+mod transaction;
+mod watch;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::os::raw::c_void;
 use std::sync::{Arc, Mutex};
 
-use self::{conversions::*, sys::*};
+use self::conversions::*;
+use self::decode::Decoders;
+use self::ratelimit::RateLimiter;
+
+pub use self::alerts::{AlertEvent, AlertState, Alerts, Condition, Threshold};
+pub use self::ambient::{AmbientLightInfo, AmbientLightParams};
+pub use self::backend::{MemoryBackend, SMCBackend};
+pub use self::backlight::KeyboardBacklight;
+#[cfg(target_os = "macos")]
+pub use self::builder::SMCBuilder;
+pub use self::cache::CachedSMC;
+pub use self::conversions::{ConversionError, HexBytes, KnownType, SMCNumber, SMCString, SMCType};
+pub use self::curve::{FanCurve, FanCurveController};
+pub use self::dashboard::Dashboard;
+use self::dashboard::DashboardLayout;
+pub use self::decode::DecodedValue;
+pub use self::electrical::{ElectricalReading, ElectricalReadings};
+pub use self::energy::{EnergyMonitor, PowerSource};
+#[cfg(target_os = "macos")]
+pub use self::events::{Events, SMCEvent, SMCEventKind};
+pub use self::fans::{
+    Fan, FanDescriptor, FanHealth, FanInfo, FanLocation, FanMode, FanOverrideGuard, FanType,
+    ManagedFans, ThermalZone,
+};
+pub use self::firmware::{FirmwareVersion, Revision};
+#[cfg(all(unix, feature = "helper"))]
+pub use self::helper::{HelperServer, RemoteSMC};
+pub use self::history::{History, SensorReading};
+#[cfg(target_os = "macos")]
+pub use self::iokit::IOKitBackend;
+pub use self::journal::WriteRecord;
+pub use self::keys::{KeyIndex, Keys, KeysInCategory, KeysMatching, KeysWithInfo};
+pub use self::logger::CsvLogger;
+pub use self::platform::Platform;
+pub use self::policy::WritePolicy;
+pub use self::power::{AdapterInfo, ChargeLimit, PowerLimits};
+pub use self::ratelimit::RateLimitStats;
+pub use self::replay::{Recorder, Replayer};
+pub use self::retry::RetryPolicy;
+pub use self::sampler::Sampler;
+pub use self::sensor::{KeySensor, Sensor, Sensors};
+pub use self::snapshot::{Snapshot, SnapshotEntry};
+pub use self::status::MotionSensorStatus;
+#[cfg(target_os = "macos")]
+pub use self::sys::io_connect_t;
+pub use self::temps::{
+    Celsius, DriveBay, DriveBayInfo, TemperatureSensor, TemperatureSensors, ThermalLimits,
+    ThermalSnapshot,
+};
+pub use self::transaction::WriteTransaction;
+#[cfg(feature = "stream")]
+pub use self::watch::WatchStream;
+pub use self::watch::{KeyChange, Watch};
 
 use four_char_code::{four_char_code, FourCharCode};
 
-use libc::{sysctl, CTL_HW};
+#[derive(Default, Debug, Copy, Clone)]
+pub struct SMCBytes(pub [u8; 32]); // 32
 
 #[derive(Default, Debug, Copy, Clone)]
-pub struct SMCBytes([u8; 32]); // 32
+pub struct SMCVal {
+    data_type: DataType,
+    bytes: SMCBytes,
+}
+
+impl SMCVal {
+    pub fn new(data_type: DataType, data: &[u8]) -> Result<SMCVal, SMCError> {
+        let mut bytes = SMCBytes::default();
+        if data.len() != data_type.size as usize || data.len() > bytes.0.len() {
+            return Err(SMCError::OutOfRange(data_type));
+        }
+        bytes.0[..data.len()].copy_from_slice(data);
+
+        Ok(SMCVal { data_type, bytes })
+    }
+
+    #[inline]
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> &SMCBytes {
+        &self.bytes
+    }
+
+    pub fn data(&self) -> &[u8] {
+        let len = std::cmp::min(self.data_type.size as usize, self.bytes.0.len());
+        &self.bytes.0[..len]
+    }
+
+    pub fn set_len(&mut self, len: usize) -> Result<(), SMCError> {
+        if len > self.bytes.0.len() {
+            return Err(SMCError::OutOfRange(DataType {
+                id: self.data_type.id,
+                size: len as u32,
+            }));
+        }
+
+        for b in self.bytes.0[len..].iter_mut() {
+            *b = 0;
+        }
+        self.data_type.size = len as u32;
+        Ok(())
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        decode_number(self.data_type, self.bytes)
+    }
+
+    pub fn as_string(&self) -> Option<String> {
+        decode_string(self.data_type, self.bytes)
+    }
+}
+
+impl PartialEq for SMCVal {
+    fn eq(&self, other: &SMCVal) -> bool {
+        self.data_type == other.data_type && self.data() == other.data()
+    }
+}
+
+impl Eq for SMCVal {}
+
+impl std::hash::Hash for SMCVal {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.data_type.hash(state);
+        self.data().hash(state);
+    }
+}
 
 // "ch8*", "char", "flag", "flt ", "fp1f", "fp6a", "fp79", "fp88", "fpe2", "hex_", "si16", "si8 ", "sp1e", "sp2d", "sp3c", "sp4b", "sp5a", "sp69", "sp78", "sp87", "ui16", "ui32", "ui8 ", "{alc", "{ali", "{alp", "{alv", "{fds", "{hdi", "{lim", "{lkb", "{lks", "{mss", "{rev"
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub struct DataType {
     pub id: FourCharCode,
     pub size: u32,
 }
 
+impl DataType {
+    #[inline]
+    pub fn known(&self) -> Option<KnownType> {
+        KnownType::from_id(self.id)
+    }
+}
+
 #[derive(Default, Debug, Copy, Clone)]
 #[repr(C)]
 pub struct SMCKey {
@@ -37,137 +226,339 @@ pub struct SMCKey {
     pub info: DataType,
 }
 
-macro_rules! fcc_format {
-    ( $fmt:literal, $( $args:expr ),+ ) => {
-        Into::<FourCharCode>::into(format!($fmt, $($args),+))
-    }
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyInfo {
+    pub data_type: DataType,
+    pub attributes: KeyAttributes,
 }
 
-#[derive(Debug, Copy, Clone)]
-#[repr(u8)]
-enum SMCSelector {
-    Unknown = 0,
-    // HandleYPCEvent = 2,
-    ReadKey = 5,
-    WriteKey = 6,
-    GetKeyFromIndex = 8,
-    GetKeyInfo = 9,
-}
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyAttributes(pub u8);
+
+impl KeyAttributes {
+    pub const PRIVATE_WRITE: KeyAttributes = KeyAttributes(0x01);
+    pub const PRIVATE_READ: KeyAttributes = KeyAttributes(0x02);
+    pub const ATOMIC: KeyAttributes = KeyAttributes(0x04);
+    pub const CONST: KeyAttributes = KeyAttributes(0x08);
+    pub const FUNCTION: KeyAttributes = KeyAttributes(0x10);
+    pub const UNKNOWN: KeyAttributes = KeyAttributes(0x20);
+    pub const WRITE: KeyAttributes = KeyAttributes(0x40);
+    pub const READ: KeyAttributes = KeyAttributes(0x80);
 
-impl Default for SMCSelector {
-    fn default() -> Self {
-        Self::Unknown
+    #[inline]
+    pub fn bits(self) -> u8 {
+        self.0
     }
-}
 
-#[derive(Default, Debug, Copy, Clone)]
-#[repr(C)]
-struct SMCVersion {
-    major: u8,
-    minor: u8,
-    build: u8,
-    reserved: u8,
-    release: u16,
-}
+    #[inline]
+    pub fn contains(self, other: KeyAttributes) -> bool {
+        self.0 & other.0 == other.0
+    }
 
-#[derive(Default, Debug, Copy, Clone)]
-#[repr(C)]
-struct SMCPLimitData {
-    version: u16,
-    length: u16,
-    cpu_plimit: u32,
-    gpu_plimit: u32,
-    mem_plimit: u32,
-}
+    #[inline]
+    pub fn is_readable(self) -> bool {
+        self.contains(KeyAttributes::READ)
+    }
 
-#[derive(Default, Debug, Copy, Clone)]
-#[repr(C)]
-struct SMCKeyInfoData {
-    data_size: u32,
-    data_type: FourCharCode,
-    data_attributes: u8,
-}
+    #[inline]
+    pub fn is_writable(self) -> bool {
+        self.contains(KeyAttributes::WRITE)
+    }
 
-#[derive(Default, Debug, Copy, Clone)]
-#[repr(C)]
-struct SMCParam {
-    key: FourCharCode,
-    vers: SMCVersion,
-    p_limit_data: SMCPLimitData,
-    key_info: SMCKeyInfoData,
-    result: u8,
-    status: u8,
-    selector: SMCSelector,
-    data32: u32,
-    bytes: SMCBytes,
-}
+    #[inline]
+    pub fn is_function(self) -> bool {
+        self.contains(KeyAttributes::FUNCTION)
+    }
 
-macro_rules! err_system {
-    ( $err:literal ) => {
-        (($err & 0x3f) << 26)
-    };
+    #[inline]
+    pub fn is_atomic(self) -> bool {
+        self.contains(KeyAttributes::ATOMIC)
+    }
+
+    #[inline]
+    pub fn is_const(self) -> bool {
+        self.contains(KeyAttributes::CONST)
+    }
+
+    #[inline]
+    pub fn is_private_read(self) -> bool {
+        self.contains(KeyAttributes::PRIVATE_READ)
+    }
+
+    #[inline]
+    pub fn is_private_write(self) -> bool {
+        self.contains(KeyAttributes::PRIVATE_WRITE)
+    }
 }
 
-macro_rules! err_sub {
-    ( $err:literal ) => {
-        (($err & 0xfff) << 14)
-    };
+impl std::ops::BitOr for KeyAttributes {
+    type Output = KeyAttributes;
+
+    fn bitor(self, other: KeyAttributes) -> KeyAttributes {
+        KeyAttributes(self.0 | other.0)
+    }
 }
 
-const SYS_IOKIT: kern_return_t = err_system!(0x38);
-const SUB_IOKIT_COMMON: kern_return_t = err_sub!(0);
+impl std::ops::BitAnd for KeyAttributes {
+    type Output = KeyAttributes;
 
-macro_rules! iokit_common_err {
-    ( $err:literal ) => {
-        SYS_IOKIT | SUB_IOKIT_COMMON | $err
-    };
+    fn bitand(self, other: KeyAttributes) -> KeyAttributes {
+        KeyAttributes(self.0 & other.0)
+    }
 }
 
-const KERN_SUCCESS: kern_return_t = 0;
-#[allow(non_upper_case_globals)]
-const kIOReturnSuccess: kern_return_t = KERN_SUCCESS;
-#[allow(non_upper_case_globals)]
-const kIOReturnNotPrivileged: kern_return_t = iokit_common_err!(0x2c1);
+impl fmt::Debug for KeyAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const NAMES: [(KeyAttributes, &str); 8] = [
+            (KeyAttributes::READ, "READ"),
+            (KeyAttributes::WRITE, "WRITE"),
+            (KeyAttributes::UNKNOWN, "UNKNOWN"),
+            (KeyAttributes::FUNCTION, "FUNCTION"),
+            (KeyAttributes::CONST, "CONST"),
+            (KeyAttributes::ATOMIC, "ATOMIC"),
+            (KeyAttributes::PRIVATE_READ, "PRIVATE_READ"),
+            (KeyAttributes::PRIVATE_WRITE, "PRIVATE_WRITE"),
+        ];
+
+        write!(f, "KeyAttributes(")?;
+        let mut first = true;
+        for (attr, name) in NAMES.iter() {
+            if self.contains(*attr) {
+                if !first {
+                    write!(f, " | ")?;
+                }
+                write!(f, "{}", name)?;
+                first = false;
+            }
+        }
+        if first {
+            write!(f, "empty")?;
+        }
+        write!(f, ")")
+    }
+}
 
-const MACH_PORT_NULL: mach_port_t = 0 as mach_port_t;
-#[allow(non_upper_case_globals)]
-const kIOMasterPortDefault: mach_port_t = MACH_PORT_NULL;
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Read,
+    Write,
+    KeyInfo,
+}
 
-const HW_PACKAGES: i32 = 125;
-const HW_PHYSICALCPU: i32 = 101;
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::Read => write!(f, "read"),
+            Operation::Write => write!(f, "write"),
+            Operation::KeyInfo => write!(f, "key info"),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum SMCError {
     DriverNotFound,
     FailedToOpen,
     KeyNotFound(FourCharCode),
+    NotReadable(FourCharCode),
+    NotWritable(FourCharCode),
+    KeySizeMismatch(FourCharCode),
+    CommCollision,
+    SpuriousData,
+    BadCommand,
+    BadParameter,
+    FramingError,
+    BadArgument,
+    Timeout,
+    KeyIndexRange,
+    BadFuncParameter,
+    DeviceAccess,
+    UnsupportedFeature,
     NotPrivileged,
+    NotOpen,
+    Busy,
+    InvalidArgument,
+    Unsupported,
     UnsafeFanSpeed,
+    UnsafeChargeLimit,
+    WriteDenied(FourCharCode),
+    FanErrors(Vec<(u32, SMCError)>),
+    WriteVerificationFailed {
+        key: FourCharCode,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+    SizeMismatch {
+        key: FourCharCode,
+        expected: u32,
+        actual: u32,
+    },
+    TypeMismatch(ConversionError),
+    OutOfRange(DataType),
+    Context {
+        operation: Operation,
+        key: FourCharCode,
+        source: Box<SMCError>,
+    },
     Unknown(i32, u8),
     Sysctl(i32),
 }
 
+const SMC_RESULT_COMM_COLLISION: u8 = 0x80;
+const SMC_RESULT_SPURIOUS_DATA: u8 = 0x81;
+const SMC_RESULT_BAD_COMMAND: u8 = 0x82;
+const SMC_RESULT_BAD_PARAMETER: u8 = 0x83;
+const SMC_RESULT_KEY_NOT_FOUND: u8 = 0x84;
+const SMC_RESULT_KEY_NOT_READABLE: u8 = 0x85;
+const SMC_RESULT_KEY_NOT_WRITABLE: u8 = 0x86;
+const SMC_RESULT_KEY_SIZE_MISMATCH: u8 = 0x87;
+const SMC_RESULT_FRAMING_ERROR: u8 = 0x88;
+const SMC_RESULT_BAD_ARGUMENT: u8 = 0x89;
+const SMC_RESULT_TIMEOUT: u8 = 0xb7;
+const SMC_RESULT_KEY_INDEX_RANGE: u8 = 0xb8;
+const SMC_RESULT_BAD_FUNC_PARAMETER: u8 = 0xc0;
+const SMC_RESULT_DEVICE_ACCESS: u8 = 0xc7;
+const SMC_RESULT_UNSUPPORTED_FEATURE: u8 = 0xcb;
+
+const IO_RETURN_TIMEOUT: i32 = 0xe000_02d6_u32 as i32;
+
 impl SMCError {
+    pub(crate) fn from_smc_result(key: FourCharCode, io_res: i32, smc_res: u8) -> SMCError {
+        match smc_res {
+            SMC_RESULT_COMM_COLLISION => SMCError::CommCollision,
+            SMC_RESULT_SPURIOUS_DATA => SMCError::SpuriousData,
+            SMC_RESULT_BAD_COMMAND => SMCError::BadCommand,
+            SMC_RESULT_BAD_PARAMETER => SMCError::BadParameter,
+            SMC_RESULT_KEY_NOT_FOUND => SMCError::KeyNotFound(key),
+            SMC_RESULT_KEY_NOT_READABLE => SMCError::NotReadable(key),
+            SMC_RESULT_KEY_NOT_WRITABLE => SMCError::NotWritable(key),
+            SMC_RESULT_KEY_SIZE_MISMATCH => SMCError::KeySizeMismatch(key),
+            SMC_RESULT_FRAMING_ERROR => SMCError::FramingError,
+            SMC_RESULT_BAD_ARGUMENT => SMCError::BadArgument,
+            SMC_RESULT_TIMEOUT => SMCError::Timeout,
+            SMC_RESULT_KEY_INDEX_RANGE => SMCError::KeyIndexRange,
+            SMC_RESULT_BAD_FUNC_PARAMETER => SMCError::BadFuncParameter,
+            SMC_RESULT_DEVICE_ACCESS => SMCError::DeviceAccess,
+            SMC_RESULT_UNSUPPORTED_FEATURE => SMCError::UnsupportedFeature,
+            _ => SMCError::Unknown(io_res, smc_res),
+        }
+    }
+
     pub fn code(&self) -> Option<FourCharCode> {
         match self {
             SMCError::KeyNotFound(code) => Some(*code),
+            SMCError::NotReadable(code) => Some(*code),
+            SMCError::NotWritable(code) => Some(*code),
+            SMCError::KeySizeMismatch(code) => Some(*code),
+            SMCError::WriteDenied(code) => Some(*code),
+            SMCError::WriteVerificationFailed { key, .. } => Some(*key),
+            SMCError::SizeMismatch { key, .. } => Some(*key),
+            SMCError::TypeMismatch(err) => err.key,
+            SMCError::Context { key, .. } => Some(*key),
             _ => None,
         }
     }
 
-    pub fn io_result(&self) -> Option<i32> {
+    pub fn operation(&self) -> Option<Operation> {
         match self {
+            SMCError::Context { operation, .. } => Some(*operation),
+            _ => None,
+        }
+    }
+
+    pub fn root(&self) -> &SMCError {
+        match self {
+            SMCError::Context { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// The error without its `Context`, for matching on the variant.
+    ///
+    /// Every error raised while reading, writing or looking up a key comes
+    /// wrapped in `Context`, so `match err { SMCError::NotPrivileged => .. }`
+    /// no longer matches; use `match err.kind() { .. }` instead.
+    #[inline]
+    pub fn kind(&self) -> &SMCError {
+        self.root()
+    }
+
+    pub fn io_result(&self) -> Option<i32> {
+        match self.root() {
             SMCError::Unknown(io_res, _) => Some(*io_res),
             _ => None,
         }
     }
 
     pub fn smc_result(&self) -> Option<u8> {
-        match self {
+        match self.root() {
+            SMCError::CommCollision => Some(SMC_RESULT_COMM_COLLISION),
+            SMCError::SpuriousData => Some(SMC_RESULT_SPURIOUS_DATA),
+            SMCError::BadCommand => Some(SMC_RESULT_BAD_COMMAND),
+            SMCError::BadParameter => Some(SMC_RESULT_BAD_PARAMETER),
+            SMCError::KeyNotFound(_) => Some(SMC_RESULT_KEY_NOT_FOUND),
+            SMCError::NotReadable(_) => Some(SMC_RESULT_KEY_NOT_READABLE),
+            SMCError::NotWritable(_) => Some(SMC_RESULT_KEY_NOT_WRITABLE),
+            SMCError::KeySizeMismatch(_) => Some(SMC_RESULT_KEY_SIZE_MISMATCH),
+            SMCError::FramingError => Some(SMC_RESULT_FRAMING_ERROR),
+            SMCError::BadArgument => Some(SMC_RESULT_BAD_ARGUMENT),
+            SMCError::Timeout => Some(SMC_RESULT_TIMEOUT),
+            SMCError::KeyIndexRange => Some(SMC_RESULT_KEY_INDEX_RANGE),
+            SMCError::BadFuncParameter => Some(SMC_RESULT_BAD_FUNC_PARAMETER),
+            SMCError::DeviceAccess => Some(SMC_RESULT_DEVICE_ACCESS),
+            SMCError::UnsupportedFeature => Some(SMC_RESULT_UNSUPPORTED_FEATURE),
             SMCError::Unknown(_, smc_res) => Some(*smc_res),
             _ => None,
         }
     }
+
+    pub fn retryable(&self) -> bool {
+        match self.root() {
+            SMCError::CommCollision
+            | SMCError::SpuriousData
+            | SMCError::FramingError
+            | SMCError::Timeout
+            | SMCError::DeviceAccess
+            | SMCError::Busy => true,
+            SMCError::Unknown(io_res, _) => *io_res == IO_RETURN_TIMEOUT,
+            _ => false,
+        }
+    }
+
+    fn context(self, operation: Operation, key: FourCharCode) -> SMCError {
+        match self {
+            SMCError::Context {
+                key: inner, source, ..
+            } if inner == key => SMCError::Context {
+                operation,
+                key,
+                source,
+            },
+            err @ SMCError::Context { .. } => err,
+            err => SMCError::Context {
+                operation,
+                key,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    pub(crate) fn is_not_found(&self) -> bool {
+        match self.kind() {
+            SMCError::KeyNotFound(_) => true,
+            _ => false,
+        }
+    }
+
+    fn for_key(self, key: FourCharCode) -> SMCError {
+        match self {
+            SMCError::TypeMismatch(mut err) => {
+                err.key = Some(key);
+                SMCError::TypeMismatch(err)
+            }
+            err => err,
+        }
+    }
 }
 
 impl fmt::Display for SMCError {
@@ -176,8 +567,67 @@ impl fmt::Display for SMCError {
             SMCError::DriverNotFound => write!(f, "Driver not found."),
             SMCError::FailedToOpen => write!(f, "Failed to open driver."),
             SMCError::KeyNotFound(code) => write!(f, "Key {:?} not found.", code),
+            SMCError::NotReadable(code) => write!(f, "Key {:?} is not readable.", code),
+            SMCError::NotWritable(code) => write!(f, "Key {:?} is not writable.", code),
+            SMCError::KeySizeMismatch(code) => write!(f, "Key {:?} size mismatch.", code),
+            SMCError::CommCollision => write!(f, "SMC communication collision."),
+            SMCError::SpuriousData => write!(f, "SMC received spurious data."),
+            SMCError::BadCommand => write!(f, "SMC rejected the command."),
+            SMCError::BadParameter => write!(f, "SMC rejected a parameter."),
+            SMCError::FramingError => write!(f, "SMC framing error."),
+            SMCError::BadArgument => write!(f, "SMC rejected an argument."),
+            SMCError::Timeout => write!(f, "SMC timed out."),
+            SMCError::KeyIndexRange => write!(f, "Key index out of range."),
+            SMCError::BadFuncParameter => write!(f, "SMC rejected a function parameter."),
+            SMCError::DeviceAccess => write!(f, "SMC device access error."),
+            SMCError::UnsupportedFeature => write!(f, "SMC feature not supported."),
             SMCError::NotPrivileged => write!(f, "You do NOT have enough privileges."),
+            SMCError::NotOpen => write!(f, "SMC connection is not open."),
+            SMCError::Busy => write!(f, "SMC driver is busy."),
+            SMCError::InvalidArgument => write!(f, "SMC driver rejected an argument."),
+            SMCError::Unsupported => write!(f, "Operation not supported by the SMC driver."),
             SMCError::UnsafeFanSpeed => write!(f, "Fan speed is unsafe to be setted."),
+            SMCError::UnsafeChargeLimit => write!(f, "Charge limit is out of range."),
+            SMCError::WriteDenied(code) => {
+                write!(f, "Writing key {:?} is denied by the write policy.", code)
+            }
+            SMCError::FanErrors(errors) => {
+                write!(f, "Failed to control fans:")?;
+                for (id, err) in errors.iter() {
+                    write!(f, " fan {}: {}", id, err)?;
+                }
+                Ok(())
+            }
+            SMCError::WriteVerificationFailed {
+                key,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Key {:?} reads back {:02x?} after writing {:02x?}.",
+                key, actual, expected
+            ),
+            SMCError::SizeMismatch {
+                key,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Key {:?} is {} bytes long, expected {}.",
+                key, actual, expected
+            ),
+            SMCError::TypeMismatch(err) => fmt::Display::fmt(err, f),
+            SMCError::Context {
+                operation,
+                key,
+                source,
+            } => write!(f, "Failed to {} key {:?}: {}", operation, key, source),
+            SMCError::OutOfRange(data_type) => write!(
+                f,
+                "Value is out of range for {:?}/{}.",
+                data_type.id.to_string(),
+                data_type.size
+            ),
             SMCError::Unknown(io_res, smc_res) => write!(
                 f,
                 "Unknown error: IOKit exited with code {} and SMC result {}.",
@@ -192,108 +642,97 @@ impl std::error::Error for SMCError {
     fn description(&self) -> &str {
         "SMC error"
     }
-}
-
-macro_rules! sysctl_errno {
-    () => {
-        SMCError::Sysctl(::std::io::Error::last_os_error().raw_os_error().unwrap())
-    };
-}
 
-fn get_cpus_number() -> Option<usize> {
-    let mut mib: [i32; 2] = [CTL_HW, HW_PACKAGES];
-    let mut num: u32 = 0;
-    let mut len: usize = std::mem::size_of::<u32>();
-
-    let res = unsafe {
-        sysctl(
-            &mut mib[0] as *mut _,
-            2,
-            &mut num as *mut _ as *mut c_void,
-            &mut len,
-            std::ptr::null_mut(),
-            0,
-        )
-    };
-    if res == -1 {
-        None
-    } else {
-        Some(num as usize)
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SMCError::Context { source, .. } => Some(&**source),
+            _ => None,
+        }
     }
 }
 
-fn get_cores_number() -> Option<usize> {
-    let mut mib: [i32; 2] = [CTL_HW, HW_PHYSICALCPU];
-    let mut num: u32 = 0;
-    let mut len: usize = std::mem::size_of::<u32>();
-
-    let res = unsafe {
-        sysctl(
-            &mut mib[0] as *mut _,
-            2,
-            &mut num as *mut _ as *mut c_void,
-            &mut len,
-            std::ptr::null_mut(),
-            0,
-        )
-    };
-    if res == -1 {
-        None
-    } else {
-        Some(num as usize)
+impl From<SMCError> for std::io::Error {
+    fn from(err: SMCError) -> std::io::Error {
+        use std::io::{Error, ErrorKind};
+
+        let kind = match err.root() {
+            SMCError::Sysctl(errno) => return Error::from_raw_os_error(*errno),
+            SMCError::DriverNotFound | SMCError::KeyNotFound(_) => ErrorKind::NotFound,
+            SMCError::NotPrivileged
+            | SMCError::NotReadable(_)
+            | SMCError::NotWritable(_)
+            | SMCError::WriteDenied(_) => ErrorKind::PermissionDenied,
+            SMCError::Timeout => ErrorKind::TimedOut,
+            SMCError::NotOpen => ErrorKind::NotConnected,
+            SMCError::BadArgument
+            | SMCError::InvalidArgument
+            | SMCError::BadParameter
+            | SMCError::BadFuncParameter
+            | SMCError::KeyIndexRange
+            | SMCError::OutOfRange(_)
+            | SMCError::UnsafeFanSpeed
+            | SMCError::UnsafeChargeLimit => ErrorKind::InvalidInput,
+            SMCError::KeySizeMismatch(_)
+            | SMCError::SizeMismatch { .. }
+            | SMCError::TypeMismatch(_)
+            | SMCError::SpuriousData
+            | SMCError::FramingError
+            | SMCError::WriteVerificationFailed { .. } => ErrorKind::InvalidData,
+            _ => ErrorKind::Other,
+        };
+
+        Error::new(kind, err)
     }
 }
 
-struct SMCRepr(Mutex<io_connect_t>);
+struct SMCRepr {
+    backend: Box<dyn SMCBackend>,
+    key_info_cache: Mutex<HashMap<FourCharCode, KeyInfo>>,
+    cache_key_info: bool,
+    write_policy: Mutex<WritePolicy>,
+    journal: Mutex<Option<Vec<WriteRecord>>>,
+    forced_fans: Mutex<()>,
+    rate_limiter: RateLimiter,
+    decoders: Decoders,
+    dashboard_layout: Mutex<Option<Arc<DashboardLayout>>>,
+    key_index_map: Mutex<Option<HashMap<FourCharCode, u32>>>,
+}
 
 impl SMCRepr {
-    fn new() -> Result<SMCRepr, SMCError> {
-        let conn: io_connect_t = kIOMasterPortDefault;
-        let result: kern_return_t;
-        let device = unsafe {
-            IOServiceGetMatchingService(
-                kIOMasterPortDefault,
-                IOServiceMatching(b"AppleSMC\0" as *const _),
-            )
-        };
-
-        if device.is_null() {
-            return Err(SMCError::DriverNotFound);
-        }
-
-        result = unsafe { IOServiceOpen(&mut *device, mach_task_self(), 0, &conn) };
-        unsafe { IOObjectRelease(&mut *device) };
-        if result != kIOReturnSuccess {
-            return Err(SMCError::FailedToOpen);
+    fn new<B: SMCBackend + 'static>(backend: B) -> SMCRepr {
+        SMCRepr {
+            backend: Box::new(backend),
+            key_info_cache: Mutex::new(HashMap::new()),
+            cache_key_info: true,
+            write_policy: Mutex::new(WritePolicy::default()),
+            journal: Mutex::new(None),
+            forced_fans: Mutex::new(()),
+            rate_limiter: RateLimiter::default(),
+            decoders: Decoders::default(),
+            dashboard_layout: Mutex::new(None),
+            key_index_map: Mutex::new(None),
         }
-
-        Ok(SMCRepr(Mutex::new(conn)))
     }
 
-    #[allow(non_upper_case_globals)]
-    fn call_driver(&self, input: &SMCParam) -> Result<SMCParam, SMCError> {
-        let mut output: SMCParam = Default::default();
-        let input_size: usize = std::mem::size_of::<SMCParam>();
-        let mut output_size: usize = std::mem::size_of::<SMCParam>();
-
-        let conn = self.0.lock().unwrap();
+    fn throttle(&self, calls: u32) {
+        self.rate_limiter.acquire(calls);
+    }
 
-        let result = unsafe {
-            IOConnectCallStructMethod(
-                *conn,
-                2,
-                input as *const _ as *const c_void,
-                input_size,
-                &mut output as *mut _ as *mut c_void,
-                &mut output_size,
-            )
-        };
+    fn read_bytes(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        self.throttle(1);
+        self.backend
+            .read(key)
+            .map_err(|e| e.context(Operation::Read, key.code))
+    }
 
-        match (result, output.result) {
-            (kIOReturnSuccess, 0) => Ok(output),
-            (kIOReturnSuccess, 132) => Err(SMCError::KeyNotFound(input.key)),
-            (kIOReturnNotPrivileged, _) => Err(SMCError::NotPrivileged),
-            _ => Err(SMCError::Unknown(result, output.result)),
+    fn check_size(key: SMCKey) -> Result<(), SMCError> {
+        match expected_size(key.info.id) {
+            Some(expected) if expected != key.info.size => Err(SMCError::SizeMismatch {
+                key: key.code,
+                expected,
+                actual: key.info.size,
+            }),
+            _ => Ok(()),
         }
     }
 
@@ -301,223 +740,396 @@ impl SMCRepr {
     where
         T: SMCType,
     {
-        let mut input: SMCParam = Default::default();
-        input.key = key.code;
-        input.key_info.data_size = key.info.size;
-        input.selector = SMCSelector::ReadKey;
+        SMCRepr::check_size(key)
+            .and_then(|_| self.read_bytes(key))
+            .and_then(|bytes| SMCType::from_smc(key.info, bytes).map_err(|e| e.for_key(key.code)))
+            .map_err(|e| e.context(Operation::Read, key.code))
+    }
+
+    fn write_bytes(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        self.write_policy
+            .lock()
+            .unwrap()
+            .check(key.code)
+            .map_err(|e| e.context(Operation::Write, key.code))?;
+
+        let mut journal = self.journal.lock().unwrap();
+        let entries = match journal.as_mut() {
+            Some(entries) => entries,
+            None => {
+                self.throttle(1);
+                return self
+                    .backend
+                    .write(key, bytes)
+                    .map_err(|e| e.context(Operation::Write, key.code));
+            }
+        };
 
-        let output = self.call_driver(&input)?;
+        self.throttle(2);
+        let old = self.backend.read(key).ok().map(|old| SMCVal {
+            data_type: key.info,
+            bytes: old,
+        });
+        self.backend
+            .write(key, bytes)
+            .map_err(|e| e.context(Operation::Write, key.code))?;
+        entries.push(WriteRecord {
+            key: key.code,
+            old,
+            new: SMCVal {
+                data_type: key.info,
+                bytes,
+            },
+            timestamp: std::time::SystemTime::now(),
+        });
 
-        Ok(SMCType::from_smc(key.info, output.bytes))
+        Ok(())
     }
 
     fn write_data<T>(&self, key: SMCKey, data: T) -> Result<(), SMCError>
     where
         T: SMCType,
     {
-        let mut input: SMCParam = Default::default();
-        input.key = key.code;
-        input.bytes = SMCType::to_smc(&data, key.info);
-        input.key_info.data_size = key.info.size;
-        input.selector = SMCSelector::WriteKey;
+        SMCRepr::check_size(key)
+            .and_then(|_| SMCType::to_smc(&data, key.info).map_err(|e| e.for_key(key.code)))
+            .and_then(|bytes| self.write_bytes(key, bytes))
+            .map_err(|e| e.context(Operation::Write, key.code))
+    }
 
-        self.call_driver(&input)?;
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        if let Some(info) = self.key_info_cache.lock().unwrap().get(&key) {
+            return Ok(*info);
+        }
 
-        Ok(())
+        self.throttle(1);
+        let info = self
+            .backend
+            .key_info(key)
+            .map_err(|e| e.context(Operation::KeyInfo, key))?;
+
+        if self.cache_key_info {
+            self.key_info_cache.lock().unwrap().insert(key, info);
+        }
+
+        Ok(info)
     }
 
-    fn key_information(&self, key: FourCharCode) -> Result<DataType, SMCError> {
-        let mut input: SMCParam = Default::default();
-        input.key = key;
-        input.selector = SMCSelector::GetKeyInfo;
+    fn clear_key_info_cache(&self) {
+        self.key_info_cache.lock().unwrap().clear();
+        *self.dashboard_layout.lock().unwrap() = None;
+        *self.key_index_map.lock().unwrap() = None;
+    }
 
-        let output = self.call_driver(&input)?;
+    fn key_information(&self, key: FourCharCode) -> Result<DataType, SMCError> {
+        Ok(self.key_info(key)?.data_type)
+    }
 
-        Ok(DataType {
-            id: output.key_info.data_type,
-            size: output.key_info.data_size,
-        })
+    /// Looks up `key` on behalf of `operation`, so a missing key is reported
+    /// as a failed read or write rather than a bare key info lookup.
+    fn key_information_for(
+        &self,
+        operation: Operation,
+        key: FourCharCode,
+    ) -> Result<DataType, SMCError> {
+        self.key_information(key)
+            .map_err(|err| err.context(operation, key))
     }
 
     fn read_key<T>(&self, code: FourCharCode) -> Result<T, SMCError>
     where
         T: SMCType,
     {
-        let info = self.key_information(code)?;
+        let info = self.key_information_for(Operation::Read, code)?;
         self.read_data(SMCKey { code, info })
     }
 
+    fn read_key_with_info<T>(&self, code: FourCharCode) -> Result<(DataType, T), SMCError>
+    where
+        T: SMCType,
+    {
+        let info = self.key_information_for(Operation::Read, code)?;
+        Ok((info, self.read_data(SMCKey { code, info })?))
+    }
+
+    fn read_key_raw(&self, code: FourCharCode) -> Result<SMCVal, SMCError> {
+        let info = self.key_information_for(Operation::Read, code)?;
+        let bytes = self.read_bytes(SMCKey { code, info })?;
+        Ok(SMCVal {
+            data_type: info,
+            bytes,
+        })
+    }
+
+    fn read_many(&self, codes: &[FourCharCode]) -> Vec<Result<SMCVal, SMCError>> {
+        let infos: Vec<Result<DataType, SMCError>> = codes
+            .iter()
+            .map(|code| self.key_information_for(Operation::Read, *code))
+            .collect();
+        let keys: Vec<SMCKey> = codes
+            .iter()
+            .zip(infos.iter())
+            .filter_map(|(code, info)| match info {
+                Ok(info) => Some(SMCKey {
+                    code: *code,
+                    info: *info,
+                }),
+                Err(_) => None,
+            })
+            .collect();
+        self.throttle(keys.len() as u32);
+        let mut values = self.backend.read_many(&keys).into_iter();
+
+        codes
+            .iter()
+            .zip(infos)
+            .map(|(code, info)| {
+                let data_type = info?;
+                let bytes = match values.next() {
+                    Some(bytes) => bytes.map_err(|e| e.context(Operation::Read, *code))?,
+                    None => return Err(SMCError::KeyNotFound(*code)),
+                };
+                Ok(SMCVal { data_type, bytes })
+            })
+            .collect()
+    }
+
     fn write_key<T>(&self, code: FourCharCode, data: T) -> Result<(), SMCError>
     where
         T: SMCType,
     {
-        let info = self.key_information(code)?;
+        let info = self.key_information_for(Operation::Write, code)?;
         self.write_data(SMCKey { code, info }, data)
     }
 
-    fn key_information_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
-        let mut input: SMCParam = Default::default();
-        input.selector = SMCSelector::GetKeyFromIndex;
-        input.data32 = index;
+    fn write_key_verified<T>(&self, code: FourCharCode, data: T) -> Result<(), SMCError>
+    where
+        T: SMCType,
+    {
+        let info = self.key_information_for(Operation::Write, code)?;
+        let key = SMCKey { code, info };
+        let bytes = SMCType::to_smc(&data, info).map_err(|e| e.for_key(code))?;
+        self.write_bytes(key, bytes)?;
+
+        let len = std::cmp::min(info.size as usize, bytes.0.len());
+        let actual = self.read_bytes(key)?;
+        if actual.0[..len] == bytes.0[..len] {
+            Ok(())
+        } else {
+            Err(SMCError::WriteVerificationFailed {
+                key: code,
+                expected: bytes.0[..len].to_vec(),
+                actual: actual.0[..len].to_vec(),
+            })
+        }
+    }
+
+    fn write_key_raw(&self, code: FourCharCode, data: &[u8]) -> Result<(), SMCError> {
+        let info = self.key_information_for(Operation::Write, code)?;
+        let mut bytes: SMCBytes = Default::default();
+        let len = std::cmp::min(data.len(), info.size as usize).min(bytes.0.len());
+        bytes.0[..len].copy_from_slice(&data[..len]);
+        self.write_bytes(SMCKey { code, info }, bytes)
+    }
 
-        let output = self.call_driver(&input)?;
+    fn try_write_key<T>(&self, code: FourCharCode, data: T) -> Result<(), SMCError>
+    where
+        T: SMCType,
+    {
+        let info = self
+            .key_info(code)
+            .map_err(|err| err.context(Operation::Write, code))?;
+        if !info.attributes.is_writable() {
+            return Err(SMCError::NotWritable(code).context(Operation::Write, code));
+        }
 
-        Ok(output.key)
+        self.write_data(
+            SMCKey {
+                code,
+                info: info.data_type,
+            },
+            data,
+        )
     }
-}
 
-impl Drop for SMCRepr {
-    fn drop(&mut self) {
-        let conn = self.0.lock().unwrap();
-        unsafe { IOServiceClose(*conn) };
+    fn key_information_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        self.throttle(1);
+        self.backend.key_at_index(index)
     }
 }
 
-unsafe impl Send for SMCRepr {}
-unsafe impl Sync for SMCRepr {}
-
+#[cfg(target_os = "macos")]
 lazy_static! {
     static ref SHARED: Mutex<Option<Arc<SMCRepr>>> = Mutex::new(None);
 }
+pub struct SMC(Arc<SMCRepr>);
 
-pub struct Fan {
-    smc_repr: Arc<SMCRepr>,
-    id: u32,
-    name: String,
-}
+impl SMC {
+    #[cfg(target_os = "macos")]
+    pub fn new() -> Result<SMC, SMCError> {
+        Ok(SMC::with_backend(IOKitBackend::new()?))
+    }
 
-impl fmt::Debug for Fan {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Fan")
-            .field("id", &self.id)
-            .field("name", &self.name)
-            .finish()
+    pub fn with_backend<B: SMCBackend + 'static>(backend: B) -> SMC {
+        SMC(Arc::new(SMCRepr::new(backend)))
+    }
+
+    #[cfg(target_os = "macos")]
+    /// # Safety
+    ///
+    /// `conn` must be a valid, open connection to the AppleSMC service. Ownership is
+    /// transferred: the connection is closed when the returned value is dropped.
+    pub unsafe fn from_raw(conn: io_connect_t) -> SMC {
+        SMC::with_backend(IOKitBackend::from_raw(conn))
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn as_raw(&self) -> Option<io_connect_t> {
+        self.0.backend.as_raw()
     }
-}
 
-impl Clone for Fan {
-    fn clone(&self) -> Fan {
-        Fan {
-            smc_repr: self.smc_repr.clone(),
-            id: self.id,
-            name: self.name.clone(),
+    #[cfg(target_os = "macos")]
+    pub fn into_raw(self) -> Result<io_connect_t, SMC> {
+        let repr = match Arc::try_unwrap(self.0) {
+            Ok(repr) => repr,
+            Err(repr) => return Err(SMC(repr)),
+        };
+        match repr.backend.detach() {
+            Some(conn) => Ok(conn),
+            None => Err(SMC(Arc::new(repr))),
         }
     }
-}
 
-impl Fan {
-    #[inline]
-    pub fn id(&self) -> u32 {
-        self.id
+    #[cfg(target_os = "macos")]
+    pub fn shared() -> Result<SMC, SMCError> {
+        let mut shared = SHARED.lock().unwrap();
+        match (*shared).as_ref() {
+            None => {
+                let smc = Arc::new(SMCRepr::new(IOKitBackend::new()?));
+                let res = smc.clone();
+                *shared = Some(smc);
+                Ok(SMC(res))
+            }
+            Some(shared) => Ok(SMC(shared.clone())),
+        }
     }
 
     #[inline]
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn read_key<T: SMCType>(&self, key: FourCharCode) -> Result<T, SMCError> {
+        self.0.read_key(key)
     }
 
-    pub fn min_speed(&self) -> Result<f64, SMCError> {
-        self.smc_repr.read_key(fcc_format!("F{}Mn", self.id))
+    pub fn read_key_strict<T: SMCNumber>(&self, key: FourCharCode) -> Result<T, SMCError> {
+        let val = self.0.read_key_raw(key)?;
+        decode_strict(val.data_type, val.bytes).map_err(|e| e.for_key(key))
     }
 
-    pub fn max_speed(&self) -> Result<f64, SMCError> {
-        self.smc_repr.read_key(fcc_format!("F{}Mx", self.id))
+    pub fn read_key_lossy<T: SMCNumber>(&self, key: FourCharCode) -> Result<T, SMCError> {
+        let val = self.0.read_key_raw(key)?;
+        decode_lossy(val.data_type, val.bytes).map_err(|e| e.for_key(key))
     }
 
-    pub fn current_speed(&self) -> Result<f64, SMCError> {
-        self.smc_repr.read_key(fcc_format!("F{}Ac", self.id))
+    #[inline]
+    pub fn read_key_with_info<T: SMCType>(
+        &self,
+        key: FourCharCode,
+    ) -> Result<(DataType, T), SMCError> {
+        self.0.read_key_with_info(key)
     }
 
-    pub fn rpm(&self) -> Result<f64, SMCError> {
-        let mut rpm = self.current_speed()? - self.min_speed()?;
-        if rpm < 0.0 {
-            rpm = 0.0;
-        }
+    pub fn read_key_raw(&self, key: FourCharCode) -> Result<(DataType, SMCVal), SMCError> {
+        let val = self.0.read_key_raw(key)?;
+        Ok((val.data_type, val))
+    }
 
-        Ok(rpm)
+    #[inline]
+    pub fn read_many(&self, keys: &[FourCharCode]) -> Vec<Result<SMCVal, SMCError>> {
+        self.0.read_many(keys)
     }
 
-    pub fn is_managed(&self) -> Result<bool, SMCError> {
-        let bitmask: u16 = self.smc_repr.read_key(four_char_code!("FS! "))?;
-        Ok(bitmask & (1_u16 << (self.id as u16)) == 0)
+    pub fn clear_key_info_cache(&self) {
+        self.0.clear_key_info_cache()
     }
 
-    pub fn set_managed(&self, what: bool) -> Result<(), SMCError> {
-        let bitmask: u16 = self.smc_repr.read_key(four_char_code!("FS! "))?;
-        let mask = 1_u16 << (self.id as u16);
-        let new: u16 = if what {
-            bitmask & !mask
-        } else {
-            bitmask | mask
-        };
+    #[inline]
+    pub fn key_info_full(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        self.0.key_info(key)
+    }
 
-        if bitmask != new {
-            self.smc_repr.write_key(four_char_code!("FS! "), new)
-        } else {
-            Ok(())
-        }
+    pub fn can_write(&self) -> Result<(), SMCError> {
+        self.0.backend.can_write()
     }
 
-    pub fn set_min_speed(&self, speed: f64) -> Result<(), SMCError> {
-        let max = self.max_speed()?;
-        if speed <= 0.0 || speed > max {
-            Err(SMCError::UnsafeFanSpeed)
-        } else {
-            self.smc_repr
-                .write_key(fcc_format!("F{}Mn", self.id), speed)
-        }
+    pub fn is_key_writable(&self, key: FourCharCode) -> Result<bool, SMCError> {
+        Ok(self.0.key_info(key)?.attributes.is_writable())
     }
 
-    pub fn set_current_speed(&self, speed: f64) -> Result<(), SMCError> {
-        let min = self.min_speed()?;
-        let max = self.max_speed()?;
-        if speed <= min || speed > max {
-            Err(SMCError::UnsafeFanSpeed)
-        } else {
-            self.set_managed(false)?;
-            self.smc_repr
-                .write_key(fcc_format!("F{}Tg", self.id), speed)
+    pub fn exists(&self, key: FourCharCode) -> Result<bool, SMCError> {
+        match self.0.key_info(key) {
+            Ok(_) => Ok(true),
+            Err(ref err) if err.is_not_found() => Ok(false),
+            Err(err) => Err(err),
         }
     }
 
-    pub fn percent(&self) -> Result<f64, SMCError> {
-        let current = self.current_speed()?;
-        let min = self.min_speed()?;
-        let max = self.max_speed()?;
+    /// Finds the enumeration index of `key` with a binary search over the
+    /// key table. Firmware that does not list keys in sorted order makes the
+    /// search miss; the first miss then walks the whole table (one SMC call
+    /// per key, about a thousand on most machines) and caches the resulting
+    /// map until `clear_key_info_cache`.
+    pub fn key_index(&self, key: FourCharCode) -> Result<u32, SMCError> {
+        if !self.exists(key)? {
+            return Err(SMCError::KeyNotFound(key));
+        }
+
+        let len = self._keys_len()?;
+        let (mut low, mut high) = (0, len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let code = self.0.key_information_at_index(mid)?;
+            if code == key {
+                return Ok(mid);
+            } else if code < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
 
-        let rpm = current - min;
-        let rpm = if rpm < 0.0 { 0.0 } else { rpm };
+        let mut map = self.0.key_index_map.lock().unwrap();
+        if map.is_none() {
+            let mut index_map = HashMap::with_capacity(len as usize);
+            for index in 0..len {
+                index_map.insert(self.0.key_information_at_index(index)?, index);
+            }
+            *map = Some(index_map);
+        }
 
-        Ok(rpm / (max - min) * 100.0)
+        map.as_ref()
+            .and_then(|map| map.get(&key).cloned())
+            .ok_or(SMCError::KeyNotFound(key))
     }
-}
 
-unsafe impl Send for Fan {}
-unsafe impl Sync for Fan {}
-
-pub struct SMC(Arc<SMCRepr>);
+    #[inline]
+    pub fn write_key<T: SMCType>(&self, key: FourCharCode, data: T) -> Result<(), SMCError> {
+        self.0.write_key(key, data)
+    }
 
-impl SMC {
-    pub fn new() -> Result<SMC, SMCError> {
-        Ok(SMC(Arc::new(SMCRepr::new()?)))
+    pub fn write_str(&self, key: FourCharCode, value: &str) -> Result<(), SMCError> {
+        self.0.write_key(key, SMCString::from(value))
     }
 
-    pub fn shared() -> Result<SMC, SMCError> {
-        let mut shared = SHARED.lock().unwrap();
-        match (*shared).as_ref() {
-            None => {
-                let smc = Arc::new(SMCRepr::new()?);
-                let res = smc.clone();
-                *shared = Some(smc);
-                Ok(SMC(res))
-            }
-            Some(shared) => Ok(SMC(shared.clone())),
-        }
+    #[inline]
+    pub fn write_key_verified<T: SMCType>(
+        &self,
+        key: FourCharCode,
+        data: T,
+    ) -> Result<(), SMCError> {
+        self.0.write_key_verified(key, data)
     }
 
     #[inline]
-    pub fn read_key<T: SMCType>(&self, key: FourCharCode) -> Result<T, SMCError> {
-        self.0.read_key(key)
+    pub fn try_write_key<T: SMCType>(&self, key: FourCharCode, data: T) -> Result<(), SMCError> {
+        self.0.try_write_key(key, data)
     }
 
     fn _keys_len(&self) -> Result<u32, SMCError> {
@@ -529,27 +1141,13 @@ impl SMC {
     }
 
     pub fn keys(&self) -> Result<Vec<FourCharCode>, SMCError> {
-        let len = self._keys_len()?;
-        let mut res: Vec<FourCharCode> = Vec::with_capacity(len as usize);
-
-        for i in 0..len {
-            res.push(self.0.key_information_at_index(i)?);
-        }
-
-        Ok(res)
+        self.keys_iter()?.collect()
     }
 
     pub fn smc_keys(&self) -> Result<Vec<SMCKey>, SMCError> {
-        let len = self._keys_len()?;
-        let mut res: Vec<SMCKey> = Vec::with_capacity(len as usize);
-
-        for i in 0..len {
-            let key = self.0.key_information_at_index(i)?;
-            let info = self.0.key_information(key)?;
-            res.push(SMCKey { code: key, info });
-        }
-
-        Ok(res)
+        self.keys_with_info()?
+            .map(|res| res.map(|(code, info)| SMCKey { code, info }))
+            .collect()
     }
 
     pub fn fans_len(&self) -> Result<usize, SMCError> {
@@ -557,12 +1155,24 @@ impl SMC {
     }
 
     pub fn fan(&self, id: u32) -> Result<Fan, SMCError> {
-        let res: RawFan = self.0.read_key(fcc_format!("F{}ID", id))?;
+        let index = KeyIndex::try_from(id)?;
+        let name = match self
+            .0
+            .read_key::<FanDescriptor>(fcc_format!("F{}ID", index))
+        {
+            Ok(res) => res.name,
+            Err(ref err) if err.is_not_found() => {
+                self.0.key_information(fcc_format!("F{}Ac", index))?;
+                String::new()
+            }
+            Err(err) => return Err(err),
+        };
 
         Ok(Fan {
             smc_repr: self.0.clone(),
             id,
-            name: res.name,
+            index,
+            name,
         })
     }
 
@@ -577,10 +1187,6 @@ impl SMC {
         Ok(res)
     }
 
-    pub fn is_optical_disk_drive_full(&self) -> Result<bool, SMCError> {
-        self.0.read_key(four_char_code!("MSDI"))
-    }
-
     pub fn all_temperature_sensors_keys(&self) -> Result<Vec<FourCharCode>, SMCError> {
         Ok(self
             .smc_keys()?
@@ -595,23 +1201,23 @@ impl SMC {
             .collect())
     }
 
-    pub fn all_temperature_sensors(&self) -> Result<HashMap<FourCharCode, f64>, SMCError> {
+    pub fn all_temperature_sensors(&self) -> Result<HashMap<FourCharCode, Celsius>, SMCError> {
         let keys = self.all_temperature_sensors_keys()?;
         let mut res = HashMap::with_capacity(keys.len());
 
         for key in keys.into_iter() {
-            res.insert(key, self.0.read_key(key)?);
+            res.insert(key, Celsius(self.0.read_key(key)?));
         }
 
         Ok(res)
     }
 
-    pub fn temperature(&self, key: FourCharCode) -> Result<f64, SMCError> {
+    pub fn temperature(&self, key: FourCharCode) -> Result<Celsius, SMCError> {
         if key.to_string().starts_with("T") {
             let info = self.0.key_information(key)?;
 
             if info.id == TYPE_SP78 || info.id == TYPE_FLT {
-                self.0.read_key(key)
+                self.0.read_key(key).map(Celsius)
             } else {
                 Err(SMCError::KeyNotFound(key))
             }
@@ -620,17 +1226,15 @@ impl SMC {
         }
     }
 
-    pub fn cpu_temperature(&self, id: u8) -> Result<f64, SMCError> {
-        self.temperature(fcc_format!("TC{}C", id))
+    pub fn cpu_temperature(&self, id: u8) -> Result<Celsius, SMCError> {
+        self.temperature(fcc_format!("TC{}C", KeyIndex::try_from(id)?))
     }
 
-    pub fn cpus_temperature(&self) -> Result<Vec<f64>, SMCError> {
-        let cores = match get_cores_number() {
-            Some(x) => x as u8,
-            None => return Err(sysctl_errno!()),
-        };
+    #[cfg(target_os = "macos")]
+    pub fn cpus_temperature(&self) -> Result<Vec<Celsius>, SMCError> {
+        let cores = hw::physical_cpus()? as u8;
 
-        let mut res: Vec<f64> = Vec::with_capacity(usize::from(cores));
+        let mut res: Vec<Celsius> = Vec::with_capacity(usize::from(cores));
 
         for i in 0..cores {
             res.push(self.cpu_temperature(i)?);
@@ -639,22 +1243,16 @@ impl SMC {
         Ok(res)
     }
 
-    pub fn package_temperature(&self, id: u8) -> Result<Vec<f64>, SMCError> {
-        let cpusno = match get_cpus_number() {
-            Some(x) => x as u8,
-            None => return Err(sysctl_errno!()),
-        };
-
-        let cores = match get_cores_number() {
-            Some(x) => x as u8,
-            None => return Err(sysctl_errno!()),
-        };
+    #[cfg(target_os = "macos")]
+    pub fn package_temperature(&self, id: u8) -> Result<Vec<Celsius>, SMCError> {
+        let cpusno = hw::cpu_packages()? as u8;
+        let cores = hw::physical_cpus()? as u8;
 
         let cpc = cores / cpusno;
         let start = cpc * id;
         let stop = start + cpc;
 
-        let mut res: Vec<f64> = Vec::with_capacity(usize::from(cpc));
+        let mut res: Vec<Celsius> = Vec::with_capacity(usize::from(cpc));
 
         for i in start..stop {
             res.push(self.cpu_temperature(i)?);
@@ -663,13 +1261,11 @@ impl SMC {
         Ok(res)
     }
 
-    pub fn packages_temperature(&self) -> Result<Vec<Vec<f64>>, SMCError> {
-        let cpusno = match get_cpus_number() {
-            Some(x) => x as u8,
-            None => return Err(sysctl_errno!()),
-        };
+    #[cfg(target_os = "macos")]
+    pub fn packages_temperature(&self) -> Result<Vec<Vec<Celsius>>, SMCError> {
+        let cpusno = hw::cpu_packages()? as u8;
 
-        let mut res: Vec<Vec<f64>> = Vec::with_capacity(usize::from(cpusno));
+        let mut res: Vec<Vec<Celsius>> = Vec::with_capacity(usize::from(cpusno));
 
         for i in 0..cpusno {
             res.push(self.package_temperature(i)?);
@@ -678,27 +1274,25 @@ impl SMC {
         Ok(res)
     }
 
-    pub fn gpu_temperature(&self, id: u8) -> Result<f64, SMCError> {
-        self.temperature(fcc_format!("FG{}C", id))
+    pub fn gpu_temperature(&self, id: u8) -> Result<Celsius, SMCError> {
+        self.temperature(fcc_format!("FG{}C", KeyIndex::try_from(id)?))
     }
 
-    pub fn gpus_temperature(&self) -> Result<Vec<f64>, SMCError> {
-        let mut res: Vec<f64> = Vec::new();
-        let mut idx: u8 = 0;
+    pub fn gpus_temperature(&self) -> Result<Vec<Celsius>, SMCError> {
+        let mut res: Vec<Celsius> = Vec::new();
 
-        loop {
-            match self.gpu_temperature(idx) {
+        for idx in KeyIndex::all() {
+            match self.temperature(fcc_format!("FG{}C", idx)) {
                 Ok(temp) => {
                     res.push(temp);
                 }
-                Err(SMCError::KeyNotFound(_)) => {
+                Err(ref err) if err.is_not_found() => {
                     break;
                 }
                 Err(err) => {
                     return Err(err);
                 }
             }
-            idx += 1;
         }
 
         Ok(res)
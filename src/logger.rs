@@ -0,0 +1,285 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+pub struct CsvLogger {
+    smc: SMC,
+    path: PathBuf,
+    keys: Vec<FourCharCode>,
+    interval: Duration,
+    max_bytes: Option<u64>,
+    max_files: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl CsvLogger {
+    pub fn new<P: AsRef<Path>>(
+        smc: SMC,
+        path: P,
+        keys: &[FourCharCode],
+        interval: Duration,
+    ) -> CsvLogger {
+        CsvLogger {
+            smc,
+            path: path.as_ref().to_path_buf(),
+            keys: keys.to_vec(),
+            interval,
+            max_bytes: None,
+            max_files: 1,
+            file: None,
+            written: 0,
+        }
+    }
+
+    pub fn rotate(mut self, max_bytes: u64, max_files: usize) -> CsvLogger {
+        self.max_bytes = Some(max_bytes);
+        self.max_files = max_files;
+        self
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[FourCharCode] {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate_files(&mut self) -> io::Result<()> {
+        self.file = None;
+
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+
+        for n in (1..self.max_files).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+
+    fn header(&self) -> String {
+        let mut line = String::from("timestamp");
+        for key in self.keys.iter() {
+            line.push(',');
+            line.push_str(key.to_string().trim_end());
+        }
+        line.push('\n');
+        line
+    }
+
+    fn row(&self) -> Result<String, SMCError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut line = format!("{}.{:03}", timestamp.as_secs(), timestamp.subsec_millis());
+
+        for key in self.keys.iter() {
+            line.push(',');
+            match self.smc.read_key_raw(*key) {
+                Ok((_, val)) => {
+                    if let Some(value) = val.as_f64() {
+                        line.push_str(&value.to_string());
+                    }
+                }
+                Err(err) => match err.kind() {
+                    SMCError::KeyNotFound(_) | SMCError::NotReadable(_) => (),
+                    _ => return Err(err),
+                },
+            }
+        }
+        line.push('\n');
+        Ok(line)
+    }
+
+    fn existing_header(&self) -> io::Result<Option<String>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        Ok(Some(line))
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let header = self.header();
+
+        // A file written for another key set would end up with mismatched
+        // columns, so move it out of the way like a full one.
+        match self.existing_header()? {
+            Some(ref existing) if !existing.is_empty() && *existing != header => {
+                self.rotate_files()?
+            }
+            _ => (),
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = file.metadata()?.len();
+        if self.written == 0 {
+            file.write_all(header.as_bytes())?;
+            self.written = header.len() as u64;
+        }
+        self.file = Some(file);
+        Ok(())
+    }
+
+    pub fn log(&mut self) -> io::Result<()> {
+        let row = self.row()?;
+
+        if self.file.is_none() {
+            self.open()?;
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            let header_len = self.header().len() as u64;
+            if self.written > header_len && self.written + row.len() as u64 > max_bytes {
+                self.rotate_files()?;
+                self.open()?;
+            }
+        }
+
+        if let Some(ref mut file) = self.file {
+            file.write_all(row.as_bytes())?;
+            self.written += row.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    fn sleep_until(deadline: Instant) {
+        let now = Instant::now();
+        if deadline > now {
+            thread::sleep(deadline - now);
+        }
+    }
+
+    pub fn run_for(&mut self, duration: Duration) -> io::Result<()> {
+        let end = Instant::now() + duration;
+        loop {
+            let next = Instant::now() + self.interval;
+            self.log()?;
+            if next >= end {
+                return Ok(());
+            }
+            CsvLogger::sleep_until(next);
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            let next = Instant::now() + self.interval;
+            self.log()?;
+            CsvLogger::sleep_until(next);
+        }
+    }
+}
+
+impl SMC {
+    pub fn csv_logger<P: AsRef<Path>>(
+        &self,
+        path: P,
+        keys: &[FourCharCode],
+        interval: Duration,
+    ) -> CsvLogger {
+        CsvLogger::new(self.clone(), path, keys, interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MemoryBackend};
+
+    use four_char_code::four_char_code;
+
+    fn smc() -> SMC {
+        let backend = MemoryBackend::new();
+        let flt = DataType {
+            id: four_char_code!("flt "),
+            size: 4,
+        };
+        backend.insert(four_char_code!("TC0P"), flt, 50.0).unwrap();
+        backend.insert(four_char_code!("TG0P"), flt, 40.0).unwrap();
+        SMC::with_backend(backend)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("smc-logger-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("log.csv")
+    }
+
+    #[test]
+    fn rotates_oversized_existing_file() {
+        let path = temp_path("size");
+        fs::write(
+            &path,
+            format!("timestamp,TC0P\n{}", "1.000,50\n".repeat(20)),
+        )
+        .unwrap();
+
+        let keys = [four_char_code!("TC0P")];
+        let mut logger = smc()
+            .csv_logger(&path, &keys, Duration::from_millis(0))
+            .rotate(64, 2);
+        logger.log().unwrap();
+
+        let rotated = fs::read_to_string(logger.rotated_path(1)).unwrap();
+        assert_eq!(rotated.lines().count(), 21);
+        let current = fs::read_to_string(&path).unwrap();
+        assert_eq!(current.lines().collect::<Vec<_>>()[0], "timestamp,TC0P");
+        assert_eq!(current.lines().count(), 2);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_key_set_starts_a_new_file() {
+        let path = temp_path("keys");
+        fs::write(&path, "timestamp,TC0P\n1.000,50\n").unwrap();
+
+        let keys = [four_char_code!("TC0P"), four_char_code!("TG0P")];
+        let mut logger = smc().csv_logger(&path, &keys, Duration::from_millis(0));
+        logger.log().unwrap();
+        logger.log().unwrap();
+
+        let rotated = fs::read_to_string(logger.rotated_path(1)).unwrap();
+        assert_eq!(rotated, "timestamp,TC0P\n1.000,50\n");
+        let current = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = current.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "timestamp,TC0P,TG0P");
+        assert!(lines[1].ends_with(",50,40"));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Exports SMC readings as `metrics` gauges.
+//!
+//! The `metrics` crate requires Rust 1.71, so enabling this feature raises
+//! the minimum supported Rust version above the crate's 1.38 baseline.
+
+use crate::{SMCError, SMC};
+
+use metrics_crate::gauge;
+
+type PowerReader = fn(&SMC) -> Result<f64, SMCError>;
+
+fn optional<T>(res: Result<T, SMCError>) -> Result<Option<T>, SMCError> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(ref err) if err.is_not_found() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn record_temperatures(smc: &SMC) -> Result<(), SMCError> {
+    for (sensor, temp) in smc.thermal_snapshot()? {
+        gauge!(
+            "smc_temperature_celsius",
+            "key" => sensor.key().to_string(),
+            "label" => sensor.label().to_string()
+        )
+        .set(f64::from(temp));
+    }
+
+    Ok(())
+}
+
+pub fn record_fans(smc: &SMC) -> Result<(), SMCError> {
+    let fans = match optional(smc.fans_lossy())? {
+        Some(fans) => fans,
+        None => return Ok(()),
+    };
+
+    for fan in fans {
+        let info = fan.info()?;
+        let id = info.id.to_string();
+        gauge!("smc_fan_rpm", "fan" => id.clone(), "name" => info.name.clone())
+            .set(info.current_speed);
+        gauge!("smc_fan_min_rpm", "fan" => id.clone(), "name" => info.name.clone())
+            .set(info.min_speed);
+        gauge!("smc_fan_max_rpm", "fan" => id.clone(), "name" => info.name.clone())
+            .set(info.max_speed);
+        if let Some(target) = info.target_speed {
+            gauge!("smc_fan_target_rpm", "fan" => id, "name" => info.name).set(target);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn record_electrical(smc: &SMC) -> Result<(), SMCError> {
+    for reading in smc.voltages()? {
+        let reading = reading?;
+        gauge!(
+            "smc_voltage_volts",
+            "key" => reading.key.to_string(),
+            "label" => reading.label
+        )
+        .set(reading.value);
+    }
+
+    for reading in smc.currents()? {
+        let reading = reading?;
+        gauge!(
+            "smc_current_amperes",
+            "key" => reading.key.to_string(),
+            "label" => reading.label
+        )
+        .set(reading.value);
+    }
+
+    let sources: [(&'static str, PowerReader); 4] = [
+        ("cpu", SMC::cpu_power),
+        ("gpu", SMC::gpu_power),
+        ("system", SMC::system_power),
+        ("dc_in", SMC::dc_in_power),
+    ];
+    for (source, read) in sources.iter() {
+        if let Some(watts) = optional(read(smc))? {
+            gauge!("smc_power_watts", "source" => *source).set(watts);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn record_all(smc: &SMC) -> Result<(), SMCError> {
+    record_temperatures(smc)?;
+    record_fans(smc)?;
+    record_electrical(smc)
+}
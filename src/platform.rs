@@ -0,0 +1,64 @@
+#[cfg(target_os = "macos")]
+use std::os::raw::c_void;
+
+use crate::SMC;
+#[cfg(target_os = "macos")]
+use crate::{hw, SMCError};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Intel,
+    AppleSilicon,
+}
+
+#[cfg(target_os = "macos")]
+pub fn model() -> Result<String, SMCError> {
+    hw::model_identifier()
+}
+
+impl Platform {
+    #[cfg(target_os = "macos")]
+    pub fn detect() -> Result<Platform, SMCError> {
+        let mut value: i32 = 0;
+        let mut len: usize = std::mem::size_of::<i32>();
+
+        match hw::sysctl_by_name(
+            b"hw.optional.arm64\0",
+            &mut value as *mut _ as *mut c_void,
+            &mut len,
+        ) {
+            Ok(()) if value != 0 => Ok(Platform::AppleSilicon),
+            Ok(()) => Ok(Platform::Intel),
+            Err(SMCError::Sysctl(libc::ENOENT)) => Ok(Platform::Intel),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl SMC {
+    #[inline]
+    pub fn platform(&self) -> Option<Platform> {
+        self.0.backend.platform()
+    }
+
+    pub(crate) fn platform_keys(
+        &self,
+        intel: &[FourCharCode],
+        apple: &[FourCharCode],
+    ) -> Vec<FourCharCode> {
+        let (first, second) = match self.platform() {
+            Some(Platform::AppleSilicon) => (apple, intel),
+            _ => (intel, apple),
+        };
+
+        let mut res: Vec<FourCharCode> = Vec::with_capacity(first.len() + second.len());
+        for key in first.iter().chain(second.iter()) {
+            if !res.contains(key) {
+                res.push(*key);
+            }
+        }
+        res
+    }
+}
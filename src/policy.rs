@@ -0,0 +1,136 @@
+use crate::{keys::glob_match, SMCError, SMC};
+
+use four_char_code::FourCharCode;
+
+const SAFE_KEYS: &[&str] = &[
+    "F*Tg", "F*Mn", "F*Md", "FS! ", "Ftst", "BCLM", "CH0B", "CH0C", "LKSB", "KSBL",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WritePolicy {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl WritePolicy {
+    pub fn deny_all() -> WritePolicy {
+        WritePolicy {
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+    }
+
+    pub fn allow_all() -> WritePolicy {
+        WritePolicy::deny_all().allow("****")
+    }
+
+    pub fn allow<S: Into<String>>(mut self, pattern: S) -> WritePolicy {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    pub fn deny<S: Into<String>>(mut self, pattern: S) -> WritePolicy {
+        self.deny.push(pattern.into());
+        self
+    }
+
+    pub fn is_allowed(&self, key: FourCharCode) -> bool {
+        let key = key.to_u32().to_be_bytes();
+        let matches = |pattern: &String| glob_match(pattern.as_bytes(), &key);
+
+        !self.deny.iter().any(matches) && self.allow.iter().any(matches)
+    }
+
+    pub(crate) fn check(&self, key: FourCharCode) -> Result<(), SMCError> {
+        if self.is_allowed(key) {
+            Ok(())
+        } else {
+            Err(SMCError::WriteDenied(key))
+        }
+    }
+}
+
+impl Default for WritePolicy {
+    fn default() -> WritePolicy {
+        SAFE_KEYS
+            .iter()
+            .fold(WritePolicy::deny_all(), |policy, key| policy.allow(*key))
+    }
+}
+
+impl SMC {
+    pub fn write_policy(&self) -> WritePolicy {
+        self.0.write_policy.lock().unwrap().clone()
+    }
+
+    pub fn set_write_policy(&self, policy: WritePolicy) {
+        *self.0.write_policy.lock().unwrap() = policy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MemoryBackend};
+
+    use four_char_code::four_char_code;
+
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+
+    fn smc() -> SMC {
+        let backend = MemoryBackend::new();
+        for key in &["F0Tg", "F1Md", "BCLM", "TC0P", "MSAL"] {
+            backend.insert(FourCharCode::from(*key), UI8, 0_u8).unwrap();
+        }
+        SMC::with_backend(backend)
+    }
+
+    fn denied(smc: &SMC, key: &str) -> bool {
+        match smc.write_key(FourCharCode::from(key), 1_u8) {
+            Ok(()) => false,
+            Err(err) => match err.kind() {
+                SMCError::WriteDenied(denied) => *denied == FourCharCode::from(key),
+                err => panic!("unexpected {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn default_allows_safe_keys_only() {
+        let smc = smc();
+
+        assert!(!denied(&smc, "F0Tg"));
+        assert!(!denied(&smc, "F1Md"));
+        assert!(!denied(&smc, "BCLM"));
+        assert!(denied(&smc, "TC0P"));
+        assert!(denied(&smc, "MSAL"));
+        assert_eq!(smc.read_key::<u8>(four_char_code!("TC0P")).unwrap(), 0);
+    }
+
+    #[test]
+    fn deny_overrides_allow() {
+        let smc = smc();
+        smc.set_write_policy(WritePolicy::allow_all().deny("F?Md").deny("T*"));
+
+        assert!(!denied(&smc, "F0Tg"));
+        assert!(denied(&smc, "F1Md"));
+        assert!(denied(&smc, "TC0P"));
+        assert!(!denied(&smc, "MSAL"));
+    }
+
+    #[test]
+    fn deny_all_and_globs() {
+        let smc = smc();
+        smc.set_write_policy(WritePolicy::deny_all());
+        assert!(denied(&smc, "F0Tg"));
+
+        smc.set_write_policy(WritePolicy::deny_all().allow("TC?P").allow("M*"));
+        assert!(!denied(&smc, "TC0P"));
+        assert!(!denied(&smc, "MSAL"));
+        assert!(denied(&smc, "BCLM"));
+        assert_eq!(smc.read_key::<u8>(four_char_code!("TC0P")).unwrap(), 1);
+    }
+}
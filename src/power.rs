@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+
+use crate::{
+    conversions::{TYPE_HEX, TYPE_U8},
+    keys::consts::{BATTERY_CHARGE, CHARGE_INHIBIT_B, CHARGE_INHIBIT_C, CHARGE_LIMIT},
+    ConversionError, KeyIndex, Platform, SMCError, SMC,
+};
+
+use four_char_code::{four_char_code, FourCharCode};
+
+pub(crate) const BATTERY_INFO_CHARGING: u8 = 0x01;
+pub(crate) const BATTERY_INFO_AC: u8 = 0x02;
+
+const CHARGE_ALLOW: u8 = 0x00;
+const CHARGE_INHIBIT: u8 = 0x02;
+
+pub(crate) const INTEL_CPU_POWER_KEYS: &[FourCharCode] =
+    &[four_char_code!("PC0C"), four_char_code!("PCPC")];
+pub(crate) const APPLE_CPU_POWER_KEYS: &[FourCharCode] =
+    &[four_char_code!("PCPT"), four_char_code!("PC0R")];
+pub(crate) const INTEL_GPU_POWER_KEYS: &[FourCharCode] = &[four_char_code!("PCPG")];
+pub(crate) const APPLE_GPU_POWER_KEYS: &[FourCharCode] =
+    &[four_char_code!("PGTR"), four_char_code!("PG0R")];
+pub(crate) const SYSTEM_POWER_KEYS: &[FourCharCode] = &[four_char_code!("PSTR")];
+const DC_IN_POWER_KEYS: &[FourCharCode] = &[four_char_code!("PDTR")];
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct AdapterInfo {
+    pub watts: Option<f64>,
+    pub volts: Option<f64>,
+    pub amps: Option<f64>,
+    pub connected: bool,
+}
+
+/// Charge limit in effect, and how it is enforced.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChargeLimit {
+    /// The firmware stops charging at this percentage (`BCLM`) on its own.
+    Firmware(u8),
+    /// Charging is inhibited (`CH0B`), which holds the battery at the given
+    /// current charge. Nothing lifts the inhibit as the battery drains, so
+    /// callers have to re-apply the limit to keep it.
+    Inhibited(u8),
+    /// The battery charges to full.
+    Unlimited,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PowerLimits {
+    pub version: u16,
+    pub cpu: u32,
+    pub gpu: u32,
+    pub memory: u32,
+}
+
+impl SMC {
+    #[inline]
+    pub fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        self.0.backend.power_limits()
+    }
+
+    fn read_number(&self, keys: &[FourCharCode]) -> Result<Option<f64>, SMCError> {
+        for key in keys.iter() {
+            match self.read_key_raw(*key) {
+                Ok((_, val)) => return Ok(val.as_f64()),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn adapter_info(&self) -> Result<AdapterInfo, SMCError> {
+        let connected = self.is_ac_connected()?;
+        if !connected {
+            return Ok(AdapterInfo::default());
+        }
+
+        let mut watts = self.read_number(&[four_char_code!("AC-W")])?;
+        let mut volts = self.read_number(&[four_char_code!("ADPV"), four_char_code!("AC-V")])?;
+        let mut amps = self.read_number(&[four_char_code!("ADPI"), four_char_code!("AC-I")])?;
+
+        match (watts, volts, amps) {
+            (None, Some(v), Some(a)) => watts = Some(v * a),
+            (Some(w), None, Some(a)) if a > 0.0 => volts = Some(w / a),
+            (Some(w), Some(v), None) if v > 0.0 => amps = Some(w / v),
+            _ => (),
+        }
+
+        Ok(AdapterInfo {
+            watts,
+            volts,
+            amps,
+            connected,
+        })
+    }
+
+    fn read_power(&self, intel: &[FourCharCode], apple: &[FourCharCode]) -> Result<f64, SMCError> {
+        let keys = self.platform_keys(intel, apple);
+        for key in keys.iter() {
+            match self.0.read_key(*key) {
+                Err(ref err) if err.is_not_found() => (),
+                res => return res,
+            }
+        }
+
+        Err(SMCError::KeyNotFound(keys[0]))
+    }
+
+    pub fn cpu_power(&self) -> Result<f64, SMCError> {
+        self.read_power(INTEL_CPU_POWER_KEYS, APPLE_CPU_POWER_KEYS)
+    }
+
+    pub fn gpu_power(&self) -> Result<f64, SMCError> {
+        self.read_power(INTEL_GPU_POWER_KEYS, APPLE_GPU_POWER_KEYS)
+    }
+
+    pub fn system_power(&self) -> Result<f64, SMCError> {
+        self.read_power(SYSTEM_POWER_KEYS, SYSTEM_POWER_KEYS)
+    }
+
+    pub fn dc_in_power(&self) -> Result<f64, SMCError> {
+        self.read_power(DC_IN_POWER_KEYS, DC_IN_POWER_KEYS)
+    }
+
+    fn battery_info(&self) -> Result<Option<u8>, SMCError> {
+        match self.0.read_key::<u8>(four_char_code!("BSIn")) {
+            Ok(info) => Ok(Some(info)),
+            Err(ref err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn battery_count(&self) -> Result<usize, SMCError> {
+        Ok(usize::from(self.0.read_key::<u8>(four_char_code!("BNum"))?))
+    }
+
+    pub fn battery_powered(&self) -> Result<bool, SMCError> {
+        self.0.read_key(four_char_code!("BATP"))
+    }
+
+    pub fn is_ac_connected(&self) -> Result<bool, SMCError> {
+        match self.battery_info()? {
+            Some(info) => Ok(info & BATTERY_INFO_AC != 0),
+            None => Ok(self.0.read_key::<i8>(four_char_code!("AC-W"))? > 0),
+        }
+    }
+
+    pub fn is_charging(&self) -> Result<bool, SMCError> {
+        match self.battery_info()? {
+            Some(info) => Ok(info & BATTERY_INFO_CHARGING != 0),
+            None => Ok(self.is_ac_connected()? && self.battery_current(0)? > 0),
+        }
+    }
+
+    pub fn battery_current(&self, id: u8) -> Result<i16, SMCError> {
+        self.0
+            .read_key(fcc_format!("B{}AC", KeyIndex::try_from(id)?))
+    }
+
+    pub fn battery_voltage(&self, id: u8) -> Result<u16, SMCError> {
+        self.0
+            .read_key(fcc_format!("B{}AV", KeyIndex::try_from(id)?))
+    }
+
+    pub fn battery_charge(&self) -> Result<u8, SMCError> {
+        self.0.read_key(BATTERY_CHARGE)
+    }
+
+    pub fn is_charge_inhibited(&self) -> Result<bool, SMCError> {
+        let (_, val) = self.read_key_raw(CHARGE_INHIBIT_B)?;
+        Ok(val.data().iter().any(|b| *b != CHARGE_ALLOW))
+    }
+
+    fn write_charge_control(&self, key: FourCharCode, value: u8) -> Result<(), SMCError> {
+        let data_type = self.0.key_information(key)?;
+        if data_type.size != 1 {
+            return Err(SMCError::SizeMismatch {
+                key,
+                expected: 1,
+                actual: data_type.size,
+            });
+        }
+        if data_type.id != TYPE_U8 && data_type.id != TYPE_HEX {
+            return Err(SMCError::TypeMismatch(ConversionError {
+                key: Some(key),
+                type_name: "u8",
+                data_type,
+                data: Vec::new(),
+            }));
+        }
+
+        self.0.write_key_raw(key, &[value])
+    }
+
+    pub fn set_charge_inhibited(&self, inhibit: bool) -> Result<(), SMCError> {
+        let value = if inhibit {
+            CHARGE_INHIBIT
+        } else {
+            CHARGE_ALLOW
+        };
+        self.write_charge_control(CHARGE_INHIBIT_B, value)?;
+        match self.write_charge_control(CHARGE_INHIBIT_C, value) {
+            Err(ref err) if err.is_not_found() => Ok(()),
+            res => res,
+        }
+    }
+
+    fn inhibit_limit(&self) -> Result<ChargeLimit, SMCError> {
+        if self.is_charge_inhibited()? {
+            Ok(ChargeLimit::Inhibited(self.battery_charge()?))
+        } else {
+            Ok(ChargeLimit::Unlimited)
+        }
+    }
+
+    /// Reports the charge limit in effect.
+    ///
+    /// Where the firmware has a limit key (`BCLM`) that value is returned.
+    /// Otherwise, as on Apple Silicon, an active charge inhibit is reported as
+    /// a limit at the current charge.
+    pub fn charge_limit(&self) -> Result<ChargeLimit, SMCError> {
+        if self.platform() != Some(Platform::AppleSilicon) {
+            match self.0.read_key(CHARGE_LIMIT) {
+                Ok(percent) => return Ok(ChargeLimit::Firmware(percent)),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        match self.inhibit_limit() {
+            Err(ref err) if err.is_not_found() => Err(SMCError::UnsupportedFeature),
+            res => res,
+        }
+    }
+
+    /// Sets the charge limit and returns the limit now in effect.
+    ///
+    /// Where `BCLM` exists the firmware enforces the limit itself. Otherwise
+    /// charging is inhibited if the battery is already at or above `percent`
+    /// and allowed below it; the result is then `Inhibited` or `Unlimited`,
+    /// and the caller must call this again as the charge changes.
+    pub fn set_charge_limit(&self, percent: u8) -> Result<ChargeLimit, SMCError> {
+        if percent == 0 || percent > 100 {
+            return Err(SMCError::UnsafeChargeLimit);
+        }
+
+        if self.platform() != Some(Platform::AppleSilicon) {
+            match self.0.write_key(CHARGE_LIMIT, percent) {
+                Ok(()) => return Ok(ChargeLimit::Firmware(percent)),
+                Err(ref err) if err.is_not_found() => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let inhibit = percent < 100 && self.battery_charge()? >= percent;
+        self.set_charge_inhibited(inhibit)?;
+        self.inhibit_limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MemoryBackend};
+
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+
+    fn apple_silicon(charge: u8) -> SMC {
+        let backend = MemoryBackend::with_platform(Platform::AppleSilicon);
+        backend.insert(BATTERY_CHARGE, UI8, charge).unwrap();
+        backend.insert(CHARGE_INHIBIT_B, UI8, CHARGE_ALLOW).unwrap();
+        SMC::with_backend(backend)
+    }
+
+    #[test]
+    fn firmware_limit() {
+        let backend = MemoryBackend::with_platform(Platform::Intel);
+        backend.insert(CHARGE_LIMIT, UI8, 100_u8).unwrap();
+        let smc = SMC::with_backend(backend);
+
+        assert_eq!(smc.charge_limit().unwrap(), ChargeLimit::Firmware(100));
+        assert_eq!(smc.set_charge_limit(80).unwrap(), ChargeLimit::Firmware(80));
+        assert_eq!(smc.charge_limit().unwrap(), ChargeLimit::Firmware(80));
+    }
+
+    #[test]
+    fn inhibit_without_ch0c() {
+        let smc = apple_silicon(85);
+
+        assert_eq!(smc.charge_limit().unwrap(), ChargeLimit::Unlimited);
+        assert_eq!(
+            smc.set_charge_limit(80).unwrap(),
+            ChargeLimit::Inhibited(85)
+        );
+        assert!(smc.is_charge_inhibited().unwrap());
+        assert_eq!(smc.charge_limit().unwrap(), ChargeLimit::Inhibited(85));
+
+        assert_eq!(smc.set_charge_limit(90).unwrap(), ChargeLimit::Unlimited);
+        assert!(!smc.is_charge_inhibited().unwrap());
+    }
+
+    #[test]
+    fn no_limit_keys() {
+        let smc = SMC::with_backend(MemoryBackend::with_platform(Platform::AppleSilicon));
+        match smc.charge_limit() {
+            Err(SMCError::UnsupportedFeature) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+}
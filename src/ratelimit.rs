@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::SMC;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimitStats {
+    pub calls: u64,
+    pub throttled: u64,
+    pub delayed: Duration,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interval: Option<Duration>,
+    next: Option<Instant>,
+    stats: RateLimitStats,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter(Mutex<State>);
+
+impl RateLimiter {
+    pub(crate) fn set_limit(&self, max_calls_per_sec: Option<u32>) {
+        let mut state = self.0.lock().unwrap();
+        state.interval = max_calls_per_sec.map(|max| {
+            let max = if max == 0 { 1 } else { max };
+            Duration::from_nanos(1_000_000_000 / u64::from(max))
+        });
+        state.next = None;
+    }
+
+    pub(crate) fn acquire(&self, calls: u32) {
+        let wait = {
+            let mut state = self.0.lock().unwrap();
+            state.stats.calls += u64::from(calls);
+            let interval = match state.interval {
+                Some(interval) => interval,
+                None => return,
+            };
+
+            let now = Instant::now();
+            let slot = match state.next {
+                Some(slot) if slot > now => slot,
+                _ => now,
+            };
+            let wait = slot - now;
+
+            state.next = Some(slot + interval * calls);
+            if wait > Duration::from_millis(0) {
+                state.stats.throttled += 1;
+                state.stats.delayed += wait;
+            }
+            wait
+        };
+
+        if wait > Duration::from_millis(0) {
+            thread::sleep(wait);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> RateLimitStats {
+        self.0.lock().unwrap().stats
+    }
+}
+
+impl SMC {
+    pub fn set_rate_limit(&self, max_calls_per_sec: Option<u32>) {
+        self.0.rate_limiter.set_limit(max_calls_per_sec)
+    }
+
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.0.rate_limiter.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataType, MemoryBackend, SMC};
+
+    use four_char_code::four_char_code;
+
+    #[test]
+    fn counts_unthrottled_calls() {
+        let backend = MemoryBackend::new();
+        let ui8 = DataType {
+            id: four_char_code!("ui8 "),
+            size: 1,
+        };
+        backend.insert(four_char_code!("FNum"), ui8, 1_u8).unwrap();
+        let smc = SMC::with_backend(backend);
+
+        smc.read_key::<u8>(four_char_code!("FNum")).unwrap();
+        let stats = smc.rate_limit_stats();
+        assert!(stats.calls > 0);
+        assert_eq!(stats.throttled, 0);
+
+        smc.set_rate_limit(Some(1_000_000));
+        let before = smc.rate_limit_stats().calls;
+        smc.read_key::<u8>(four_char_code!("FNum")).unwrap();
+        assert!(smc.rate_limit_stats().calls > before);
+    }
+}
@@ -0,0 +1,559 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::{
+    DataType, KeyAttributes, KeyInfo, Platform, PowerLimits, SMCBackend, SMCBytes, SMCError, SMCKey,
+};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Request {
+    KeyInfo(u32),
+    Read(u32),
+    Write(u32),
+    KeyAtIndex(u32),
+    PowerLimits,
+    CanWrite,
+}
+
+impl Request {
+    fn key(self) -> FourCharCode {
+        match self {
+            Request::KeyInfo(key) | Request::Read(key) | Request::Write(key) => FourCharCode(key),
+            Request::KeyAtIndex(_) | Request::PowerLimits | Request::CanWrite => {
+                FourCharCode::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Failure {
+    KeyNotFound,
+    NotReadable,
+    NotWritable,
+    KeySizeMismatch,
+    NotPrivileged,
+    KeyIndexRange,
+    Unsupported,
+    Timeout,
+    Busy,
+    DeviceAccess,
+    Unknown(i32, u8),
+}
+
+impl Failure {
+    fn from_error(err: &SMCError) -> Failure {
+        match err.root() {
+            SMCError::KeyNotFound(_) => Failure::KeyNotFound,
+            SMCError::NotReadable(_) => Failure::NotReadable,
+            SMCError::NotWritable(_) => Failure::NotWritable,
+            SMCError::KeySizeMismatch(_) => Failure::KeySizeMismatch,
+            SMCError::NotPrivileged => Failure::NotPrivileged,
+            SMCError::KeyIndexRange => Failure::KeyIndexRange,
+            SMCError::UnsupportedFeature => Failure::Unsupported,
+            SMCError::Timeout => Failure::Timeout,
+            SMCError::Busy => Failure::Busy,
+            SMCError::DeviceAccess => Failure::DeviceAccess,
+            err => Failure::Unknown(err.io_result().unwrap_or(0), err.smc_result().unwrap_or(0)),
+        }
+    }
+
+    fn into_error(self, key: FourCharCode) -> SMCError {
+        match self {
+            Failure::KeyNotFound => SMCError::KeyNotFound(key),
+            Failure::NotReadable => SMCError::NotReadable(key),
+            Failure::NotWritable => SMCError::NotWritable(key),
+            Failure::KeySizeMismatch => SMCError::KeySizeMismatch(key),
+            Failure::NotPrivileged => SMCError::NotPrivileged,
+            Failure::KeyIndexRange => SMCError::KeyIndexRange,
+            Failure::Unsupported => SMCError::UnsupportedFeature,
+            Failure::Timeout => SMCError::Timeout,
+            Failure::Busy => SMCError::Busy,
+            Failure::DeviceAccess => SMCError::DeviceAccess,
+            Failure::Unknown(io_res, smc_res) => SMCError::Unknown(io_res, smc_res),
+        }
+    }
+
+    fn encode(self) -> String {
+        match self {
+            Failure::KeyNotFound => "err not-found".to_string(),
+            Failure::NotReadable => "err not-readable".to_string(),
+            Failure::NotWritable => "err not-writable".to_string(),
+            Failure::KeySizeMismatch => "err size-mismatch".to_string(),
+            Failure::NotPrivileged => "err not-privileged".to_string(),
+            Failure::KeyIndexRange => "err index-range".to_string(),
+            Failure::Unsupported => "err unsupported".to_string(),
+            Failure::Timeout => "err timeout".to_string(),
+            Failure::Busy => "err busy".to_string(),
+            Failure::DeviceAccess => "err device-access".to_string(),
+            Failure::Unknown(io_res, smc_res) => {
+                format!("err unknown {:08x} {:02x}", io_res as u32, smc_res)
+            }
+        }
+    }
+
+    fn decode<'a, I: Iterator<Item = &'a str>>(mut tokens: I) -> Option<Failure> {
+        Some(match tokens.next()? {
+            "not-found" => Failure::KeyNotFound,
+            "not-readable" => Failure::NotReadable,
+            "not-writable" => Failure::NotWritable,
+            "size-mismatch" => Failure::KeySizeMismatch,
+            "not-privileged" => Failure::NotPrivileged,
+            "index-range" => Failure::KeyIndexRange,
+            "unsupported" => Failure::Unsupported,
+            "timeout" => Failure::Timeout,
+            "busy" => Failure::Busy,
+            "device-access" => Failure::DeviceAccess,
+            "unknown" => Failure::Unknown(
+                u32::from_str_radix(tokens.next()?, 16).ok()? as i32,
+                u8::from_str_radix(tokens.next()?, 16).ok()?,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Response {
+    KeyInfo(KeyInfo),
+    Bytes(SMCBytes),
+    Key(FourCharCode),
+    Limits(PowerLimits),
+    Done,
+    Failed(Failure),
+}
+
+fn encode_platform(platform: Option<Platform>) -> &'static str {
+    match platform {
+        Some(Platform::Intel) => "intel",
+        Some(Platform::AppleSilicon) => "apple-silicon",
+        None => "unknown",
+    }
+}
+
+fn decode_platform(s: &str) -> Option<Option<Platform>> {
+    match s {
+        "intel" => Some(Some(Platform::Intel)),
+        "apple-silicon" => Some(Some(Platform::AppleSilicon)),
+        "unknown" => Some(None),
+        _ => None,
+    }
+}
+
+fn encode_bytes(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "-".to_string();
+    }
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_payload(s: &str) -> Option<Vec<u8>> {
+    if s == "-" {
+        return Some(Vec::new());
+    }
+
+    let s = s.as_bytes();
+    if s.len() % 2 != 0 || s.len() / 2 > SMCBytes::default().0.len() {
+        return None;
+    }
+    s.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn decode_bytes(s: &str) -> Option<SMCBytes> {
+    let payload = decode_payload(s)?;
+    let mut bytes = SMCBytes::default();
+    bytes.0[..payload.len()].copy_from_slice(&payload);
+    Some(bytes)
+}
+
+// `<op> <arg> [payload] ok ...|err ...`; only writes carry a payload
+fn parse_line(line: &str) -> Option<(Request, Option<Vec<u8>>, Response)> {
+    let mut tokens = line.split_whitespace();
+    let op = tokens.next()?;
+    let arg = u32::from_str_radix(tokens.next()?, 16).ok()?;
+    let request = match op {
+        "info" => Request::KeyInfo(arg),
+        "read" => Request::Read(arg),
+        "write" => Request::Write(arg),
+        "index" => Request::KeyAtIndex(arg),
+        "limits" => Request::PowerLimits,
+        "access" => Request::CanWrite,
+        _ => return None,
+    };
+
+    let payload = match request {
+        Request::Write(_) => Some(decode_payload(tokens.next()?)?),
+        _ => None,
+    };
+
+    let response = match tokens.next()? {
+        "err" => Response::Failed(Failure::decode(tokens)?),
+        "ok" => match request {
+            Request::KeyInfo(_) => Response::KeyInfo(KeyInfo {
+                data_type: DataType {
+                    id: FourCharCode(u32::from_str_radix(tokens.next()?, 16).ok()?),
+                    size: tokens.next()?.parse().ok()?,
+                },
+                attributes: KeyAttributes(u8::from_str_radix(tokens.next()?, 16).ok()?),
+            }),
+            Request::Read(_) => Response::Bytes(decode_bytes(tokens.next()?)?),
+            Request::Write(_) | Request::CanWrite => Response::Done,
+            Request::KeyAtIndex(_) => {
+                Response::Key(FourCharCode(u32::from_str_radix(tokens.next()?, 16).ok()?))
+            }
+            Request::PowerLimits => Response::Limits(PowerLimits {
+                version: tokens.next()?.parse().ok()?,
+                cpu: tokens.next()?.parse().ok()?,
+                gpu: tokens.next()?.parse().ok()?,
+                memory: tokens.next()?.parse().ok()?,
+            }),
+        },
+        _ => return None,
+    };
+
+    Some((request, payload, response))
+}
+
+pub struct Recorder<B> {
+    backend: B,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl<B: SMCBackend> Recorder<B> {
+    pub fn new<W: Write + Send + 'static>(backend: B, mut out: W) -> Recorder<B> {
+        let _ = writeln!(out, "platform {}", encode_platform(backend.platform()));
+        Recorder {
+            backend,
+            out: Mutex::new(Box::new(out)),
+        }
+    }
+
+    pub fn create<P: AsRef<Path>>(backend: B, path: P) -> io::Result<Recorder<B>> {
+        Ok(Recorder::new(backend, BufWriter::new(File::create(path)?)))
+    }
+
+    #[inline]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn into_inner(self) -> B {
+        let _ = self.out.lock().unwrap().flush();
+        self.backend
+    }
+
+    fn record<T, F>(&self, request: String, res: &Result<T, SMCError>, f: F)
+    where
+        F: FnOnce(&T) -> String,
+    {
+        let response = match res {
+            Ok(value) => f(value),
+            Err(err) => Failure::from_error(err).encode(),
+        };
+
+        let mut out = self.out.lock().unwrap();
+        let _ = writeln!(out, "{} {}", request, response).and_then(|_| out.flush());
+    }
+}
+
+impl<B: SMCBackend> SMCBackend for Recorder<B> {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        let res = self.backend.key_info(key);
+        self.record(format!("info {:08x}", key.to_u32()), &res, |info| {
+            format!(
+                "ok {:08x} {} {:02x}",
+                info.data_type.id.to_u32(),
+                info.data_type.size,
+                info.attributes.bits()
+            )
+        });
+        res
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        let res = self.backend.read(key);
+        let len = std::cmp::min(key.info.size as usize, SMCBytes::default().0.len());
+        self.record(format!("read {:08x}", key.code.to_u32()), &res, |bytes| {
+            format!("ok {}", encode_bytes(&bytes.0[..len]))
+        });
+        res
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        let len = std::cmp::min(key.info.size as usize, bytes.0.len());
+        let request = format!(
+            "write {:08x} {}",
+            key.code.to_u32(),
+            encode_bytes(&bytes.0[..len])
+        );
+        let res = self.backend.write(key, bytes);
+        self.record(request, &res, |_| "ok".to_string());
+        res
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        let res = self.backend.key_at_index(index);
+        self.record(format!("index {:08x}", index), &res, |key| {
+            format!("ok {:08x}", key.to_u32())
+        });
+        res
+    }
+
+    fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        let res = self.backend.power_limits();
+        self.record("limits 00000000".to_string(), &res, |limits| {
+            format!(
+                "ok {} {} {} {}",
+                limits.version, limits.cpu, limits.gpu, limits.memory
+            )
+        });
+        res
+    }
+
+    fn can_write(&self) -> Result<(), SMCError> {
+        let res = self.backend.can_write();
+        self.record("access 00000000".to_string(), &res, |_| "ok".to_string());
+        res
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        self.backend.platform()
+    }
+}
+
+struct Entry {
+    payload: Option<Vec<u8>>,
+    response: Response,
+}
+
+// Writes are checked against the payload recorded with them and every write
+// made is kept, in order, for `writes()`.
+pub struct Replayer {
+    platform: Option<Platform>,
+    responses: Mutex<HashMap<Request, VecDeque<Entry>>>,
+    writes: Mutex<Vec<(FourCharCode, Vec<u8>)>>,
+}
+
+impl Replayer {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Replayer> {
+        Replayer::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Replayer> {
+        let mut platform = None;
+        let mut responses: HashMap<Request, VecDeque<Entry>> = HashMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed trace entry at line {}", i + 1),
+                )
+            };
+
+            if line.starts_with("platform ") {
+                platform =
+                    decode_platform(line["platform ".len()..].trim()).ok_or_else(malformed)?;
+                continue;
+            }
+
+            let (request, payload, response) = parse_line(line).ok_or_else(malformed)?;
+            responses
+                .entry(request)
+                .or_default()
+                .push_back(Entry { payload, response });
+        }
+
+        Ok(Replayer {
+            platform,
+            responses: Mutex::new(responses),
+            writes: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn writes(&self) -> Vec<(FourCharCode, Vec<u8>)> {
+        self.writes.lock().unwrap().clone()
+    }
+
+    fn next(&self, request: Request) -> Option<(Option<Vec<u8>>, Response)> {
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses.get_mut(&request)?;
+        if queue.len() > 1 {
+            queue
+                .pop_front()
+                .map(|entry| (entry.payload, entry.response))
+        } else {
+            Some((queue[0].payload.clone(), queue[0].response))
+        }
+    }
+
+    fn replay(&self, request: Request) -> Result<Response, SMCError> {
+        match self.next(request) {
+            Some((_, Response::Failed(failure))) => Err(failure.into_error(request.key())),
+            Some((_, response)) => Ok(response),
+            None => Err(Failure::KeyNotFound.into_error(request.key())),
+        }
+    }
+}
+
+impl SMCBackend for Replayer {
+    fn key_info(&self, key: FourCharCode) -> Result<KeyInfo, SMCError> {
+        match self.replay(Request::KeyInfo(key.to_u32()))? {
+            Response::KeyInfo(info) => Ok(info),
+            _ => Err(SMCError::SpuriousData),
+        }
+    }
+
+    fn read(&self, key: SMCKey) -> Result<SMCBytes, SMCError> {
+        match self.replay(Request::Read(key.code.to_u32()))? {
+            Response::Bytes(bytes) => Ok(bytes),
+            _ => Err(SMCError::SpuriousData),
+        }
+    }
+
+    fn write(&self, key: SMCKey, bytes: SMCBytes) -> Result<(), SMCError> {
+        let len = std::cmp::min(key.info.size as usize, bytes.0.len());
+        let actual = bytes.0[..len].to_vec();
+        self.writes.lock().unwrap().push((key.code, actual.clone()));
+
+        let (expected, response) = match self.next(Request::Write(key.code.to_u32())) {
+            Some(entry) => entry,
+            None => return Err(SMCError::KeyNotFound(key.code)),
+        };
+        match expected {
+            Some(ref expected) if *expected != actual => {
+                return Err(SMCError::WriteVerificationFailed {
+                    key: key.code,
+                    expected: expected.clone(),
+                    actual,
+                })
+            }
+            _ => (),
+        }
+
+        match response {
+            Response::Done => Ok(()),
+            Response::Failed(failure) => Err(failure.into_error(key.code)),
+            _ => Err(SMCError::SpuriousData),
+        }
+    }
+
+    fn key_at_index(&self, index: u32) -> Result<FourCharCode, SMCError> {
+        match self.replay(Request::KeyAtIndex(index))? {
+            Response::Key(key) => Ok(key),
+            _ => Err(SMCError::SpuriousData),
+        }
+    }
+
+    fn power_limits(&self) -> Result<PowerLimits, SMCError> {
+        match self.next(Request::PowerLimits) {
+            Some((_, Response::Limits(limits))) => Ok(limits),
+            Some((_, Response::Failed(failure))) => {
+                Err(failure.into_error(FourCharCode::default()))
+            }
+            Some(_) => Err(SMCError::SpuriousData),
+            None => Err(SMCError::UnsupportedFeature),
+        }
+    }
+
+    fn can_write(&self) -> Result<(), SMCError> {
+        match self.next(Request::CanWrite) {
+            Some((_, Response::Failed(failure))) => {
+                Err(failure.into_error(FourCharCode::default()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryBackend, WritePolicy, SMC};
+    use std::sync::Arc;
+
+    use four_char_code::four_char_code;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const UI16: DataType = DataType {
+        id: four_char_code!("ui16"),
+        size: 2,
+    };
+
+    #[test]
+    fn record_then_replay() {
+        let backend = MemoryBackend::with_platform(Platform::AppleSilicon);
+        backend
+            .insert(four_char_code!("F0Tg"), UI16, 1200_u16)
+            .unwrap();
+        let trace = Shared::default();
+        let smc = SMC::with_backend(Recorder::new(backend, trace.clone()));
+        smc.set_write_policy(WritePolicy::allow_all());
+
+        assert_eq!(smc.read_key::<u16>(four_char_code!("F0Tg")).unwrap(), 1200);
+        smc.write_key(four_char_code!("F0Tg"), 1800_u16).unwrap();
+        assert!(smc.read_key::<u16>(four_char_code!("F0Mx")).is_err());
+        assert!(smc.power_limits().is_err());
+
+        let trace = String::from_utf8(trace.0.lock().unwrap().clone()).unwrap();
+        let mut lines = trace.lines();
+        assert_eq!(lines.next(), Some("platform apple-silicon"));
+        for line in lines {
+            assert!(parse_line(line).is_some(), "unparsable {:?}", line);
+        }
+
+        let replayer = Replayer::from_reader(trace.as_bytes()).unwrap();
+        assert_eq!(replayer.platform(), Some(Platform::AppleSilicon));
+        let smc = SMC::with_backend(replayer);
+        smc.set_write_policy(WritePolicy::allow_all());
+
+        assert_eq!(smc.read_key::<u16>(four_char_code!("F0Tg")).unwrap(), 1200);
+        match smc.write_key(four_char_code!("F0Tg"), 2400_u16) {
+            Err(err) => match err.root() {
+                SMCError::WriteVerificationFailed { expected, .. } => {
+                    assert_eq!(*expected, vec![0x07, 0x08])
+                }
+                err => panic!("unexpected {:?}", err),
+            },
+            Ok(()) => panic!("mismatched write accepted"),
+        }
+        smc.write_key(four_char_code!("F0Tg"), 1800_u16).unwrap();
+        match smc.read_key::<u16>(four_char_code!("F0Mx")) {
+            Err(err) => match err.root() {
+                SMCError::KeyNotFound(_) => (),
+                err => panic!("unexpected {:?}", err),
+            },
+            Ok(_) => panic!("missing key read"),
+        }
+        assert!(smc.power_limits().is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::SMCError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, backoff: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff,
+        }
+    }
+
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(1, Duration::from_millis(0))
+    }
+
+    pub fn run<T, F>(&self, mut f: F) -> Result<T, SMCError>
+    where
+        F: FnMut() -> Result<T, SMCError>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match f() {
+                Err(ref err) if err.retryable() && attempt < self.max_attempts => {
+                    thread::sleep(backoff);
+                    backoff = backoff.checked_mul(2).unwrap_or(backoff);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::none()
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{SMCError, SensorReading, SMC};
+
+use four_char_code::FourCharCode;
+
+struct Control {
+    keys: Vec<FourCharCode>,
+    paused: bool,
+    stopped: bool,
+}
+
+struct Shared {
+    control: Mutex<Control>,
+    wakeup: Condvar,
+}
+
+pub struct Sampler {
+    shared: Arc<Shared>,
+    interval: Duration,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Sampler {
+    pub fn new(
+        smc: SMC,
+        keys: &[FourCharCode],
+        interval: Duration,
+    ) -> (Sampler, Receiver<Result<SensorReading, SMCError>>) {
+        let (tx, rx) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            control: Mutex::new(Control {
+                keys: keys.to_vec(),
+                paused: false,
+                stopped: false,
+            }),
+            wakeup: Condvar::new(),
+        });
+
+        let thread_shared = shared.clone();
+        let handle = thread::spawn(move || Sampler::run(smc, &thread_shared, interval, &tx));
+
+        (
+            Sampler {
+                shared,
+                interval,
+                handle: Some(handle),
+            },
+            rx,
+        )
+    }
+
+    fn run(
+        smc: SMC,
+        shared: &Shared,
+        interval: Duration,
+        tx: &Sender<Result<SensorReading, SMCError>>,
+    ) {
+        let mut control = shared.control.lock().unwrap();
+        loop {
+            while control.paused && !control.stopped {
+                control = shared.wakeup.wait(control).unwrap();
+            }
+            if control.stopped {
+                return;
+            }
+
+            let keys = control.keys.clone();
+            drop(control);
+
+            let deadline = Instant::now() + interval;
+            for key in keys {
+                if tx.send(smc.read_sensor(key)).is_err() {
+                    return;
+                }
+            }
+
+            control = shared.control.lock().unwrap();
+            loop {
+                let now = Instant::now();
+                if control.stopped || control.paused || now >= deadline {
+                    break;
+                }
+                control = shared
+                    .wakeup
+                    .wait_timeout(control, deadline - now)
+                    .unwrap()
+                    .0;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn keys(&self) -> Vec<FourCharCode> {
+        self.shared.control.lock().unwrap().keys.clone()
+    }
+
+    pub fn add_key(&self, key: FourCharCode) {
+        let mut control = self.shared.control.lock().unwrap();
+        if !control.keys.contains(&key) {
+            control.keys.push(key);
+        }
+    }
+
+    pub fn remove_key(&self, key: FourCharCode) -> bool {
+        let mut control = self.shared.control.lock().unwrap();
+        let len = control.keys.len();
+        control.keys.retain(|k| *k != key);
+        control.keys.len() != len
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.control.lock().unwrap().paused
+    }
+
+    pub fn pause(&self) {
+        self.shared.control.lock().unwrap().paused = true;
+        self.shared.wakeup.notify_all();
+    }
+
+    pub fn resume(&self) {
+        self.shared.control.lock().unwrap().paused = false;
+        self.shared.wakeup.notify_all();
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.control.lock().unwrap().stopped = true;
+        self.shared.wakeup.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl SMC {
+    pub fn sampler(
+        &self,
+        keys: &[FourCharCode],
+        interval: Duration,
+    ) -> (Sampler, Receiver<Result<SensorReading, SMCError>>) {
+        Sampler::new(self.clone(), keys, interval)
+    }
+}
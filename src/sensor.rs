@@ -0,0 +1,166 @@
+use crate::{
+    keydb::{self, KeyCategory},
+    ConversionError, Fan, SMCError, TemperatureSensor, SMC,
+};
+
+use four_char_code::FourCharCode;
+
+pub trait Sensor: Send + Sync {
+    fn key(&self) -> FourCharCode;
+    fn label(&self) -> String;
+    fn unit(&self) -> Option<&'static str>;
+    fn read(&self, smc: &SMC) -> Result<f64, SMCError>;
+}
+
+impl Sensor for TemperatureSensor {
+    fn key(&self) -> FourCharCode {
+        TemperatureSensor::key(self)
+    }
+
+    fn label(&self) -> String {
+        TemperatureSensor::label(self).to_string()
+    }
+
+    fn unit(&self) -> Option<&'static str> {
+        Some("°C")
+    }
+
+    fn read(&self, smc: &SMC) -> Result<f64, SMCError> {
+        TemperatureSensor::read(self, smc).map(f64::from)
+    }
+}
+
+impl Sensor for Fan {
+    fn key(&self) -> FourCharCode {
+        fcc_format!("F{}Ac", self.index)
+    }
+
+    fn label(&self) -> String {
+        if self.name().is_empty() {
+            format!("Fan {}", self.id())
+        } else {
+            self.name().to_string()
+        }
+    }
+
+    fn unit(&self) -> Option<&'static str> {
+        Some("rpm")
+    }
+
+    fn read(&self, _smc: &SMC) -> Result<f64, SMCError> {
+        self.current_speed()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySensor {
+    key: FourCharCode,
+    label: String,
+    unit: Option<&'static str>,
+}
+
+impl KeySensor {
+    pub fn new(key: FourCharCode) -> KeySensor {
+        match keydb::lookup(key) {
+            Some(desc) => KeySensor {
+                key,
+                label: desc.description.to_string(),
+                unit: desc.unit,
+            },
+            None => KeySensor {
+                key,
+                label: key.to_string(),
+                unit: None,
+            },
+        }
+    }
+
+    pub fn with_label<S: Into<String>>(
+        key: FourCharCode,
+        label: S,
+        unit: Option<&'static str>,
+    ) -> KeySensor {
+        KeySensor {
+            key,
+            label: label.into(),
+            unit,
+        }
+    }
+}
+
+impl Sensor for KeySensor {
+    fn key(&self) -> FourCharCode {
+        self.key
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn unit(&self) -> Option<&'static str> {
+        self.unit
+    }
+
+    fn read(&self, smc: &SMC) -> Result<f64, SMCError> {
+        let (data_type, val) = smc.read_key_raw(self.key)?;
+        val.as_f64().ok_or_else(|| {
+            SMCError::TypeMismatch(ConversionError {
+                key: Some(self.key),
+                type_name: "f64",
+                data_type,
+                data: val.data().to_vec(),
+            })
+        })
+    }
+}
+
+pub struct Sensors(std::vec::IntoIter<Box<dyn Sensor>>);
+
+impl Iterator for Sensors {
+    type Item = Box<dyn Sensor>;
+
+    fn next(&mut self) -> Option<Box<dyn Sensor>> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Sensors {}
+
+impl SMC {
+    pub fn sensors(&self) -> Result<Sensors, SMCError> {
+        let mut res: Vec<Box<dyn Sensor>> = Vec::new();
+
+        for sensor in self.temperature_sensors()? {
+            res.push(Box::new(sensor));
+        }
+
+        match self.fans_lossy() {
+            Ok(fans) => {
+                for fan in fans {
+                    res.push(Box::new(fan));
+                }
+            }
+            Err(ref err) if err.is_not_found() => (),
+            Err(err) => return Err(err),
+        }
+
+        for key in self.keys()? {
+            match keydb::lookup(key) {
+                Some(desc) if desc.unit.is_some() => match desc.category {
+                    KeyCategory::Voltage
+                    | KeyCategory::Current
+                    | KeyCategory::Power
+                    | KeyCategory::Battery => res.push(Box::new(KeySensor::new(key))),
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+
+        Ok(Sensors(res.into_iter()))
+    }
+}
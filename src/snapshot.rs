@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use crate::{DataType, KeyInfo, SMCError, SMCKey, SMCVal, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone)]
+pub struct SnapshotEntry {
+    pub key: FourCharCode,
+    pub info: KeyInfo,
+    pub value: Option<SMCVal>,
+}
+
+impl SnapshotEntry {
+    #[inline]
+    pub fn data_type(&self) -> DataType {
+        self.info.data_type
+    }
+
+    pub fn data(&self) -> Option<&[u8]> {
+        self.value.as_ref().map(SMCVal::data)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value.as_ref().and_then(SMCVal::as_f64)
+    }
+
+    pub fn as_string(&self) -> Option<String> {
+        self.value.as_ref().and_then(SMCVal::as_string)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    entries: BTreeMap<FourCharCode, SnapshotEntry>,
+}
+
+impl Snapshot {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, key: FourCharCode) -> Option<&SnapshotEntry> {
+        self.entries.get(&key)
+    }
+
+    #[inline]
+    pub fn contains(&self, key: FourCharCode) -> bool {
+        self.entries.contains_key(&key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SnapshotEntry> {
+        self.entries.values()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = FourCharCode> + '_ {
+        self.entries.keys().cloned()
+    }
+
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a SnapshotEntry> {
+        self.entries
+            .values()
+            .filter(move |e| e.key.to_string().starts_with(prefix))
+    }
+
+    pub fn of_type(&self, id: FourCharCode) -> impl Iterator<Item = &SnapshotEntry> {
+        self.entries
+            .values()
+            .filter(move |e| e.info.data_type.id == id)
+    }
+
+    pub fn diff<'a>(
+        &'a self,
+        other: &'a Snapshot,
+    ) -> Vec<(
+        FourCharCode,
+        Option<&'a SnapshotEntry>,
+        Option<&'a SnapshotEntry>,
+    )> {
+        let mut res = Vec::new();
+
+        for (key, entry) in self.entries.iter() {
+            match other.entries.get(key) {
+                Some(other_entry) => {
+                    if entry.info != other_entry.info || entry.data() != other_entry.data() {
+                        res.push((*key, Some(entry), Some(other_entry)));
+                    }
+                }
+                None => res.push((*key, Some(entry), None)),
+            }
+        }
+
+        for (key, entry) in other.entries.iter() {
+            if !self.entries.contains_key(key) {
+                res.push((*key, None, Some(entry)));
+            }
+        }
+
+        res.sort_by_key(|(key, _, _)| *key);
+        res
+    }
+
+    pub fn insert(&mut self, entry: SnapshotEntry) -> Option<SnapshotEntry> {
+        self.entries.insert(entry.key, entry)
+    }
+}
+
+impl SMC {
+    pub fn snapshot(&self) -> Result<Snapshot, SMCError> {
+        let len = self._keys_len()?;
+        let mut res = Snapshot::default();
+
+        for i in 0..len {
+            let key = self.0.key_information_at_index(i)?;
+            let info = self.0.key_info(key)?;
+            let value = self
+                .0
+                .read_bytes(SMCKey {
+                    code: key,
+                    info: info.data_type,
+                })
+                .ok()
+                .map(|bytes| SMCVal {
+                    data_type: info.data_type,
+                    bytes,
+                });
+
+            res.insert(SnapshotEntry { key, info, value });
+        }
+
+        Ok(res)
+    }
+}
+
+impl<'a> IntoIterator for &'a Snapshot {
+    type Item = &'a SnapshotEntry;
+    type IntoIter = std::collections::btree_map::Values<'a, FourCharCode, SnapshotEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.values()
+    }
+}
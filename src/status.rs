@@ -0,0 +1,26 @@
+use crate::{SMCError, SMC};
+
+use four_char_code::{four_char_code, FourCharCode};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MotionSensorStatus {
+    /// Sudden motion protection is armed.
+    pub enabled: bool,
+    /// A fall or shock was detected and the drive heads are parked.
+    pub triggered: bool,
+}
+
+impl SMC {
+    fn read_status(&self, key: FourCharCode) -> Result<bool, SMCError> {
+        let (_, val) = self.read_key_raw(key)?;
+        Ok(val.data().iter().any(|b| *b != 0))
+    }
+
+    pub fn is_optical_disk_drive_full(&self) -> Result<bool, SMCError> {
+        self.0.read_key(four_char_code!("MSDI"))
+    }
+
+    pub fn is_lid_closed(&self) -> Result<bool, SMCError> {
+        self.read_status(four_char_code!("MSLD"))
+    }
+}
@@ -1,8 +1,9 @@
 #![allow(non_camel_case_types)]
 #![allow(non_upper_case_globals)]
 #![allow(non_snake_case)]
+#![allow(dead_code)]
 
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_void};
 
 #[repr(C)]
 pub struct __CFDictionary(c_void);
@@ -18,18 +19,125 @@ pub type io_connect_t = io_object_t;
 pub type task_t = *mut c_void;
 pub type task_port_t = task_t;
 pub type io_service_t = io_object_t;
+pub type io_iterator_t = io_object_t;
+pub type IONotificationPortRef = *mut c_void;
+pub type dispatch_queue_t = *mut c_void;
+
+pub type IOServiceInterestCallback = extern "C" fn(
+    refcon: *mut c_void,
+    service: io_service_t,
+    messageType: u32,
+    messageArgument: *mut c_void,
+);
+
+macro_rules! err_system {
+    ( $err:literal ) => {
+        (($err & 0x3f) << 26)
+    };
+}
+
+macro_rules! err_sub {
+    ( $err:literal ) => {
+        (($err & 0xfff) << 14)
+    };
+}
+
+macro_rules! iokit_common_err {
+    ( $err:literal ) => {
+        SYS_IOKIT | SUB_IOKIT_COMMON | $err
+    };
+}
+
+pub const MACH_PORT_NULL: mach_port_t = 0 as mach_port_t;
+// Both kIOMainPortDefault and the deprecated kIOMasterPortDefault are
+// MACH_PORT_NULL; using the value avoids linking a symbol missing before 12.0.
+pub const kIOMainPortDefault: mach_port_t = MACH_PORT_NULL;
+
+pub const KERN_SUCCESS: kern_return_t = 0;
+pub const MACH_SEND_INVALID_DEST: kern_return_t = 0x1000_0003;
+
+pub const SYS_IOKIT: kern_return_t = err_system!(0x38);
+pub const SUB_IOKIT_COMMON: kern_return_t = err_sub!(0);
+
+pub const kIOReturnSuccess: kern_return_t = KERN_SUCCESS;
+pub const kIOReturnError: kern_return_t = iokit_common_err!(0x2bc);
+pub const kIOReturnNoMemory: kern_return_t = iokit_common_err!(0x2bd);
+pub const kIOReturnNoResources: kern_return_t = iokit_common_err!(0x2be);
+pub const kIOReturnIPCError: kern_return_t = iokit_common_err!(0x2bf);
+pub const kIOReturnNoDevice: kern_return_t = iokit_common_err!(0x2c0);
+pub const kIOReturnNotPrivileged: kern_return_t = iokit_common_err!(0x2c1);
+pub const kIOReturnBadArgument: kern_return_t = iokit_common_err!(0x2c2);
+pub const kIOReturnLockedRead: kern_return_t = iokit_common_err!(0x2c3);
+pub const kIOReturnLockedWrite: kern_return_t = iokit_common_err!(0x2c4);
+pub const kIOReturnExclusiveAccess: kern_return_t = iokit_common_err!(0x2c5);
+pub const kIOReturnBadMessageID: kern_return_t = iokit_common_err!(0x2c6);
+pub const kIOReturnUnsupported: kern_return_t = iokit_common_err!(0x2c7);
+pub const kIOReturnVMError: kern_return_t = iokit_common_err!(0x2c8);
+pub const kIOReturnInternalError: kern_return_t = iokit_common_err!(0x2c9);
+pub const kIOReturnIOError: kern_return_t = iokit_common_err!(0x2ca);
+pub const kIOReturnCannotLock: kern_return_t = iokit_common_err!(0x2cc);
+pub const kIOReturnNotOpen: kern_return_t = iokit_common_err!(0x2cd);
+pub const kIOReturnNotReadable: kern_return_t = iokit_common_err!(0x2ce);
+pub const kIOReturnNotWritable: kern_return_t = iokit_common_err!(0x2cf);
+pub const kIOReturnNotAligned: kern_return_t = iokit_common_err!(0x2d0);
+pub const kIOReturnBadMedia: kern_return_t = iokit_common_err!(0x2d1);
+pub const kIOReturnStillOpen: kern_return_t = iokit_common_err!(0x2d2);
+pub const kIOReturnRLDError: kern_return_t = iokit_common_err!(0x2d3);
+pub const kIOReturnDMAError: kern_return_t = iokit_common_err!(0x2d4);
+pub const kIOReturnBusy: kern_return_t = iokit_common_err!(0x2d5);
+pub const kIOReturnTimeout: kern_return_t = iokit_common_err!(0x2d6);
+pub const kIOReturnOffline: kern_return_t = iokit_common_err!(0x2d7);
+pub const kIOReturnNotReady: kern_return_t = iokit_common_err!(0x2d8);
+pub const kIOReturnNotAttached: kern_return_t = iokit_common_err!(0x2d9);
+pub const kIOReturnNoChannels: kern_return_t = iokit_common_err!(0x2da);
+pub const kIOReturnNoSpace: kern_return_t = iokit_common_err!(0x2db);
+pub const kIOReturnPortExists: kern_return_t = iokit_common_err!(0x2dd);
+pub const kIOReturnCannotWire: kern_return_t = iokit_common_err!(0x2de);
+pub const kIOReturnNoInterrupt: kern_return_t = iokit_common_err!(0x2df);
+pub const kIOReturnNoFrames: kern_return_t = iokit_common_err!(0x2e0);
+pub const kIOReturnMessageTooLarge: kern_return_t = iokit_common_err!(0x2e1);
+pub const kIOReturnNotPermitted: kern_return_t = iokit_common_err!(0x2e2);
+pub const kIOReturnNoPower: kern_return_t = iokit_common_err!(0x2e3);
+pub const kIOReturnNoMedia: kern_return_t = iokit_common_err!(0x2e4);
+pub const kIOReturnUnformattedMedia: kern_return_t = iokit_common_err!(0x2e5);
+pub const kIOReturnUnsupportedMode: kern_return_t = iokit_common_err!(0x2e6);
+pub const kIOReturnUnderrun: kern_return_t = iokit_common_err!(0x2e7);
+pub const kIOReturnOverrun: kern_return_t = iokit_common_err!(0x2e8);
+pub const kIOReturnDeviceError: kern_return_t = iokit_common_err!(0x2e9);
+pub const kIOReturnNoCompletion: kern_return_t = iokit_common_err!(0x2ea);
+pub const kIOReturnAborted: kern_return_t = iokit_common_err!(0x2eb);
+pub const kIOReturnNoBandwidth: kern_return_t = iokit_common_err!(0x2ec);
+pub const kIOReturnNotResponding: kern_return_t = iokit_common_err!(0x2ed);
+pub const kIOReturnIsoTooOld: kern_return_t = iokit_common_err!(0x2ee);
+pub const kIOReturnIsoTooNew: kern_return_t = iokit_common_err!(0x2ef);
+pub const kIOReturnNotFound: kern_return_t = iokit_common_err!(0x2f0);
+pub const kIOReturnInvalid: kern_return_t = iokit_common_err!(0x1);
 
 extern "C" {
     pub fn mach_task_self() -> mach_port_t;
+
+    pub fn dispatch_queue_create(label: *const c_char, attr: *const c_void) -> dispatch_queue_t;
+    pub fn dispatch_release(object: *mut c_void);
+    pub fn dispatch_sync_f(
+        queue: dispatch_queue_t,
+        context: *mut c_void,
+        work: extern "C" fn(*mut c_void),
+    );
 }
 
 #[link(name = "IOKit", kind = "framework")]
 extern "C" {
     pub fn IOServiceMatching(name: *const u8) -> CFMutableDictionaryRef;
     pub fn IOServiceGetMatchingService(
-        masterPort: mach_port_t,
+        mainPort: mach_port_t,
         matching: CFDictionaryRef,
     ) -> io_service_t;
+    pub fn IOServiceGetMatchingServices(
+        mainPort: mach_port_t,
+        matching: CFDictionaryRef,
+        existing: *mut io_iterator_t,
+    ) -> kern_return_t;
+    pub fn IOIteratorNext(iterator: io_iterator_t) -> io_object_t;
     pub fn IOObjectRelease(object: io_object_t) -> kern_return_t;
     pub fn IOServiceOpen(
         service: io_service_t,
@@ -38,6 +146,20 @@ extern "C" {
         connect: *const io_connect_t,
     ) -> kern_return_t;
     pub fn IOServiceClose(connect: io_connect_t) -> kern_return_t;
+    pub fn IONotificationPortCreate(mainPort: mach_port_t) -> IONotificationPortRef;
+    pub fn IONotificationPortDestroy(notify: IONotificationPortRef);
+    pub fn IONotificationPortSetDispatchQueue(
+        notify: IONotificationPortRef,
+        queue: dispatch_queue_t,
+    );
+    pub fn IOServiceAddInterestNotification(
+        notifyPort: IONotificationPortRef,
+        service: io_service_t,
+        interestType: *const c_char,
+        callback: IOServiceInterestCallback,
+        refCon: *mut c_void,
+        notification: *mut io_object_t,
+    ) -> kern_return_t;
     pub fn IOConnectCallStructMethod(
         connection: mach_port_t,
         selector: u32,
@@ -0,0 +1,387 @@
+use crate::{
+    conversions::{TYPE_FLT, TYPE_HDI, TYPE_LIM, TYPE_SP78},
+    keydb, Fan, FanLocation, SMCError, ThermalZone, SMC,
+};
+
+use four_char_code::{four_char_code, FourCharCode};
+use std::fmt;
+
+const CPU_ZONE_SENSORS: &[FourCharCode] = &[
+    four_char_code!("TC0P"),
+    four_char_code!("TC0D"),
+    four_char_code!("TC0E"),
+    four_char_code!("TC0F"),
+    four_char_code!("Tp09"),
+    four_char_code!("Tp0T"),
+    four_char_code!("Tp01"),
+];
+
+const STORAGE_SENSORS: &[FourCharCode] = &[
+    four_char_code!("TH0x"),
+    four_char_code!("TH0a"),
+    four_char_code!("TH0b"),
+    four_char_code!("TaLP"),
+    four_char_code!("TaRF"),
+    four_char_code!("THSP"),
+];
+
+const CPU_PREFIXES: &[&str] = &["TC", "Tp", "TP", "Th", "TM"];
+const GPU_PREFIXES: &[&str] = &["TG", "Tg", "Th"];
+const FRONT_PREFIXES: &[&str] = &["TA", "TH", "Ta", "TO", "Tm"];
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Celsius(pub f32);
+
+impl Celsius {
+    #[inline]
+    pub fn value(self) -> f32 {
+        self.0
+    }
+
+    pub fn to_fahrenheit(self) -> f32 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    pub fn to_kelvin(self) -> f32 {
+        self.0 + 273.15
+    }
+}
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)?;
+        write!(f, " °C")
+    }
+}
+
+impl From<Celsius> for f32 {
+    fn from(value: Celsius) -> f32 {
+        value.0
+    }
+}
+
+impl From<Celsius> for f64 {
+    fn from(value: Celsius) -> f64 {
+        f64::from(value.0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DriveBayInfo {
+    pub bay: u8,
+    pub present: bool,
+    pub sensor: Option<FourCharCode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriveBay {
+    pub key: FourCharCode,
+    pub info: DriveBayInfo,
+    pub temperature: Option<Celsius>,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ThermalLimits {
+    pub cpu: u8,
+    pub gpu: u8,
+    pub memory: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureSensor {
+    key: FourCharCode,
+    label: String,
+}
+
+impl TemperatureSensor {
+    pub fn new(key: FourCharCode) -> TemperatureSensor {
+        let label = match keydb::lookup(key) {
+            Some(desc) => desc.description.to_string(),
+            None => key.to_string(),
+        };
+
+        TemperatureSensor { key, label }
+    }
+
+    pub fn with_label<S: Into<String>>(key: FourCharCode, label: S) -> TemperatureSensor {
+        TemperatureSensor {
+            key,
+            label: label.into(),
+        }
+    }
+
+    pub fn cpu_proximity() -> TemperatureSensor {
+        TemperatureSensor::new(four_char_code!("TC0P"))
+    }
+
+    pub fn gpu_proximity() -> TemperatureSensor {
+        TemperatureSensor::new(four_char_code!("TG0P"))
+    }
+
+    pub fn ambient() -> TemperatureSensor {
+        TemperatureSensor::new(four_char_code!("TA0P"))
+    }
+
+    pub fn heatsink() -> TemperatureSensor {
+        TemperatureSensor::new(four_char_code!("Th0H"))
+    }
+
+    pub fn battery() -> TemperatureSensor {
+        TemperatureSensor::new(four_char_code!("TB0T"))
+    }
+
+    #[inline]
+    pub fn key(&self) -> FourCharCode {
+        self.key
+    }
+
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn read(&self, smc: &SMC) -> Result<Celsius, SMCError> {
+        smc.temperature(self.key)
+    }
+}
+
+pub struct TemperatureSensors(std::vec::IntoIter<FourCharCode>);
+
+impl Iterator for TemperatureSensors {
+    type Item = TemperatureSensor;
+
+    fn next(&mut self) -> Option<TemperatureSensor> {
+        self.0.next().map(TemperatureSensor::new)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for TemperatureSensors {}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThermalSnapshot(Vec<(TemperatureSensor, Celsius)>);
+
+impl ThermalSnapshot {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, key: FourCharCode) -> Option<Celsius> {
+        self.0
+            .iter()
+            .find(|(sensor, _)| sensor.key() == key)
+            .map(|(_, temp)| *temp)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (TemperatureSensor, Celsius)> {
+        self.0.iter()
+    }
+
+    pub fn hottest(&self) -> Option<&(TemperatureSensor, Celsius)> {
+        let mut res: Option<&(TemperatureSensor, Celsius)> = None;
+        for reading in self.0.iter() {
+            match res {
+                Some((_, temp)) if *temp >= reading.1 => (),
+                _ => res = Some(reading),
+            }
+        }
+        res
+    }
+}
+
+impl<'a> IntoIterator for &'a ThermalSnapshot {
+    type Item = &'a (TemperatureSensor, Celsius);
+    type IntoIter = std::slice::Iter<'a, (TemperatureSensor, Celsius)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for ThermalSnapshot {
+    type Item = (TemperatureSensor, Celsius);
+    type IntoIter = std::vec::IntoIter<(TemperatureSensor, Celsius)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl SMC {
+    pub fn temperature_sensors(&self) -> Result<TemperatureSensors, SMCError> {
+        let keys: Vec<FourCharCode> = self
+            .smc_keys()?
+            .into_iter()
+            .filter_map(|k| {
+                if k.code.to_string().starts_with('T')
+                    && (k.info.id == TYPE_SP78 || k.info.id == TYPE_FLT)
+                {
+                    Some(k.code)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(TemperatureSensors(keys.into_iter()))
+    }
+
+    fn is_temperature_key(&self, key: FourCharCode) -> Result<bool, SMCError> {
+        match self.0.key_information(key) {
+            Ok(info) => Ok(info.id == TYPE_SP78 || info.id == TYPE_FLT),
+            Err(ref err) if err.is_not_found() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn gpu_temps(&self) -> Result<Vec<(TemperatureSensor, Celsius)>, SMCError> {
+        let mut res = Vec::new();
+        for sensor in self.temperature_sensors()? {
+            let key = sensor.key().to_string();
+            if key.starts_with("TG") || key.starts_with("Tg") {
+                let temp = sensor.read(self)?;
+                res.push((sensor, temp));
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn storage_temps(&self) -> Result<Vec<(TemperatureSensor, Celsius)>, SMCError> {
+        let mut res = Vec::new();
+        for sensor in self.temperature_sensors()? {
+            if sensor.key().to_string().starts_with("TH") || STORAGE_SENSORS.contains(&sensor.key())
+            {
+                let temp = sensor.read(self)?;
+                res.push((sensor, temp));
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn thermal_snapshot(&self) -> Result<ThermalSnapshot, SMCError> {
+        let mut res = Vec::new();
+        for sensor in self.temperature_sensors()? {
+            match sensor.read(self) {
+                Ok(temp) => res.push((sensor, temp)),
+                Err(err) => match err.kind() {
+                    SMCError::KeyNotFound(_)
+                    | SMCError::NotReadable(_)
+                    | SMCError::TypeMismatch(_) => (),
+                    _ => return Err(err),
+                },
+            }
+        }
+
+        Ok(ThermalSnapshot(res))
+    }
+
+    pub fn drive_bays(&self) -> Result<Vec<DriveBay>, SMCError> {
+        let mut res = Vec::new();
+        for entry in self.keys_with_info()? {
+            let (key, data_type) = entry?;
+            if data_type.id != TYPE_HDI {
+                continue;
+            }
+
+            let info: DriveBayInfo = self.0.read_key(key)?;
+            let temperature = match info.sensor {
+                Some(sensor) if info.present => match self.temperature(sensor) {
+                    Ok(temp) => Some(temp),
+                    Err(ref err) if err.is_not_found() => None,
+                    Err(err) => return Err(err),
+                },
+                _ => None,
+            };
+
+            res.push(DriveBay {
+                key,
+                info,
+                temperature,
+            });
+        }
+
+        Ok(res)
+    }
+
+    pub fn thermal_limits(&self) -> Result<ThermalLimits, SMCError> {
+        for entry in self.keys_with_info()? {
+            let (key, data_type) = entry?;
+            if data_type.id == TYPE_LIM {
+                return self.0.read_key(key);
+            }
+        }
+
+        Err(SMCError::UnsupportedFeature)
+    }
+
+    pub fn hottest(&self) -> Result<Option<(TemperatureSensor, Celsius)>, SMCError> {
+        Ok(self.thermal_snapshot()?.hottest().cloned())
+    }
+
+    pub fn zone_sensor(&self, zone: ThermalZone) -> Result<Option<TemperatureSensor>, SMCError> {
+        match zone {
+            ThermalZone::Cpu => {
+                for key in CPU_ZONE_SENSORS {
+                    if self.is_temperature_key(*key)? {
+                        return Ok(Some(TemperatureSensor::new(*key)));
+                    }
+                }
+                Ok(None)
+            }
+            ThermalZone::Gpu => Ok(self.gpu_temps()?.into_iter().next().map(|(s, _)| s)),
+            ThermalZone::Unknown(_) => Ok(None),
+        }
+    }
+
+    pub fn fan_thermal_sources(&self, fan_id: u32) -> Result<Vec<TemperatureSensor>, SMCError> {
+        let (zone, location) = match self.fan(fan_id)?.descriptor() {
+            Ok(desc) => (desc.thermal_zone(), desc.location),
+            Err(ref err) if err.is_not_found() => {
+                (ThermalZone::Unknown(0), FanLocation::Unknown(0))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let (preferred, prefixes) = match zone {
+            ThermalZone::Cpu => (CPU_ZONE_SENSORS, CPU_PREFIXES),
+            ThermalZone::Gpu => (&[][..], GPU_PREFIXES),
+            ThermalZone::Unknown(_) if location.is_front() => (STORAGE_SENSORS, FRONT_PREFIXES),
+            ThermalZone::Unknown(_) => (CPU_ZONE_SENSORS, CPU_PREFIXES),
+        };
+
+        let available: Vec<FourCharCode> = self.temperature_sensors()?.map(|s| s.key()).collect();
+        let mut res: Vec<FourCharCode> = preferred
+            .iter()
+            .filter(|key| available.contains(key))
+            .cloned()
+            .collect();
+
+        for prefix in prefixes {
+            let mut matching: Vec<FourCharCode> = available
+                .iter()
+                .filter(|key| key.to_string().starts_with(prefix) && !res.contains(key))
+                .cloned()
+                .collect();
+            // keys documented in the registry are better known than their siblings
+            matching.sort_by_key(|key| keydb::lookup(*key).is_none());
+            res.extend(matching);
+        }
+
+        Ok(res.into_iter().map(TemperatureSensor::new).collect())
+    }
+
+    pub fn fan_sensor(&self, fan: &Fan) -> Result<Option<TemperatureSensor>, SMCError> {
+        self.zone_sensor(fan.thermal_zone()?)
+    }
+}
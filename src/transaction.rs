@@ -0,0 +1,159 @@
+use crate::{conversions::SMCType, SMCBytes, SMCError, SMCKey, SMC};
+
+use four_char_code::FourCharCode;
+
+pub struct WriteTransaction {
+    smc: SMC,
+    saved: Vec<(SMCKey, SMCBytes)>,
+    done: bool,
+}
+
+impl WriteTransaction {
+    fn save(&mut self, code: FourCharCode) -> Result<SMCKey, SMCError> {
+        let info = self.smc.0.key_information(code)?;
+        let key = SMCKey { code, info };
+
+        if !self.saved.iter().any(|(saved, _)| saved.code == code) {
+            let bytes = self.smc.0.read_bytes(key)?;
+            self.saved.push((key, bytes));
+        }
+
+        Ok(key)
+    }
+
+    pub fn write_key<T: SMCType>(&mut self, key: FourCharCode, data: T) -> Result<(), SMCError> {
+        let key = self.save(key)?;
+        self.smc.0.write_data(key, data)
+    }
+
+    pub fn write_key_raw(&mut self, key: FourCharCode, data: &[u8]) -> Result<(), SMCError> {
+        self.save(key)?;
+        self.smc.0.write_key_raw(key, data)
+    }
+
+    pub fn keys(&self) -> Vec<FourCharCode> {
+        self.saved.iter().map(|(key, _)| key.code).collect()
+    }
+
+    pub fn commit(mut self) {
+        self.done = true;
+    }
+
+    fn restore(&mut self) -> Result<(), SMCError> {
+        self.done = true;
+
+        let mut res = Ok(());
+        while let Some((key, bytes)) = self.saved.pop() {
+            if let Err(err) = self.smc.0.write_bytes(key, bytes) {
+                if res.is_ok() {
+                    res = Err(err);
+                }
+            }
+        }
+        res
+    }
+
+    pub fn rollback(mut self) -> Result<(), SMCError> {
+        self.restore()
+    }
+}
+
+impl Drop for WriteTransaction {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.restore();
+        }
+    }
+}
+
+impl SMC {
+    pub fn transaction(&self) -> WriteTransaction {
+        WriteTransaction {
+            smc: self.clone(),
+            saved: Vec::new(),
+            done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DataType, MemoryBackend, SMC};
+
+    use four_char_code::four_char_code;
+
+    const FLT: DataType = DataType {
+        id: four_char_code!("flt "),
+        size: 4,
+    };
+    const UI8: DataType = DataType {
+        id: four_char_code!("ui8 "),
+        size: 1,
+    };
+
+    fn smc() -> SMC {
+        let backend = MemoryBackend::new();
+        backend
+            .insert(four_char_code!("F0Tg"), FLT, 1200.0)
+            .unwrap();
+        backend.insert(four_char_code!("F0Md"), UI8, 0_u8).unwrap();
+        SMC::with_backend(backend)
+    }
+
+    #[test]
+    fn drop_restores_saved_bytes() {
+        let smc = smc();
+        let (_, before) = smc.read_key_raw(four_char_code!("F0Tg")).unwrap();
+
+        {
+            let mut tx = smc.transaction();
+            tx.write_key(four_char_code!("F0Tg"), 3000.0).unwrap();
+            tx.write_key(four_char_code!("F0Tg"), 4000.0).unwrap();
+            tx.write_key_raw(four_char_code!("F0Md"), &[1]).unwrap();
+            assert_eq!(
+                tx.keys(),
+                vec![four_char_code!("F0Tg"), four_char_code!("F0Md")]
+            );
+            assert_eq!(
+                smc.read_key::<f32>(four_char_code!("F0Tg")).unwrap(),
+                4000.0
+            );
+            assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 1);
+        }
+
+        let (_, after) = smc.read_key_raw(four_char_code!("F0Tg")).unwrap();
+        assert_eq!(after.data(), before.data());
+        assert_eq!(
+            smc.read_key::<f32>(four_char_code!("F0Tg")).unwrap(),
+            1200.0
+        );
+        assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 0);
+    }
+
+    #[test]
+    fn commit_keeps_new_values() {
+        let smc = smc();
+
+        let mut tx = smc.transaction();
+        tx.write_key(four_char_code!("F0Tg"), 3000.0).unwrap();
+        tx.write_key(four_char_code!("F0Md"), 1_u8).unwrap();
+        tx.commit();
+
+        assert_eq!(
+            smc.read_key::<f32>(four_char_code!("F0Tg")).unwrap(),
+            3000.0
+        );
+        assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 1);
+    }
+
+    #[test]
+    fn rollback_restores_saved_bytes() {
+        let smc = smc();
+
+        let mut tx = smc.transaction();
+        tx.write_key(four_char_code!("F0Md"), 1_u8).unwrap();
+        tx.rollback().unwrap();
+
+        assert_eq!(smc.read_key::<u8>(four_char_code!("F0Md")).unwrap(), 0);
+    }
+}
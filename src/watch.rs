@@ -0,0 +1,232 @@
+use std::collections::{HashMap, VecDeque};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{SMCError, SMCVal, SMC};
+
+use four_char_code::FourCharCode;
+
+#[derive(Debug, Copy, Clone)]
+pub struct KeyChange {
+    pub key: FourCharCode,
+    pub old: Option<SMCVal>,
+    pub new: SMCVal,
+    pub timestamp: SystemTime,
+}
+
+pub struct Watch {
+    smc: SMC,
+    keys: Vec<FourCharCode>,
+    interval: Duration,
+    last: HashMap<FourCharCode, SMCVal>,
+    pending: VecDeque<Result<KeyChange, SMCError>>,
+    next_poll: Option<Instant>,
+}
+
+impl Watch {
+    #[inline]
+    pub fn keys(&self) -> &[FourCharCode] {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn wait(&mut self) {
+        if let Some(next_poll) = self.next_poll {
+            let now = Instant::now();
+            if next_poll > now {
+                thread::sleep(next_poll - now);
+            }
+        }
+        self.next_poll = Some(Instant::now() + self.interval);
+    }
+
+    fn poll(&mut self) {
+        let timestamp = SystemTime::now();
+
+        for key in self.keys.iter() {
+            match self.smc.read_key_raw(*key) {
+                Ok((_, new)) => {
+                    let old = self.last.get(key).cloned();
+                    match old {
+                        Some(ref old) if *old == new => (),
+                        _ => {
+                            self.last.insert(*key, new);
+                            self.pending.push_back(Ok(KeyChange {
+                                key: *key,
+                                old,
+                                new,
+                                timestamp,
+                            }));
+                        }
+                    }
+                }
+                Err(err) => self.pending.push_back(Err(err)),
+            }
+        }
+    }
+}
+
+impl Iterator for Watch {
+    type Item = Result<KeyChange, SMCError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+
+            if self.keys.is_empty() {
+                return None;
+            }
+
+            self.wait();
+            self.poll();
+        }
+    }
+}
+
+impl SMC {
+    pub fn watch<I>(&self, keys: I, interval: Duration) -> Watch
+    where
+        I: IntoIterator<Item = FourCharCode>,
+    {
+        Watch {
+            smc: self.clone(),
+            keys: keys.into_iter().collect(),
+            interval,
+            last: HashMap::new(),
+            pending: VecDeque::new(),
+            next_poll: None,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+mod stream {
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver, TryRecvError};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::Duration;
+
+    use futures_core::Stream;
+
+    use super::KeyChange;
+    use crate::{SMCError, SMC};
+
+    use four_char_code::FourCharCode;
+
+    pub struct WatchStream {
+        receiver: Receiver<Result<KeyChange, SMCError>>,
+        waker: Arc<Mutex<Option<Waker>>>,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl Drop for WatchStream {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Stream for WatchStream {
+        type Item = Result<KeyChange, SMCError>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+            match self.receiver.try_recv() {
+                Ok(item) => Poll::Ready(Some(item)),
+                Err(TryRecvError::Empty) => Poll::Pending,
+                Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            }
+        }
+    }
+
+    impl SMC {
+        pub fn watch_stream<I>(&self, keys: I, interval: Duration) -> WatchStream
+        where
+            I: IntoIterator<Item = FourCharCode>,
+        {
+            let (sender, receiver) = mpsc::channel();
+            let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+            let stop = Arc::new(AtomicBool::new(false));
+            let mut watch = self.watch(keys, interval);
+
+            let thread_waker = waker.clone();
+            let thread_stop = stop.clone();
+            thread::spawn(move || {
+                // checked every interval, so a dropped stream stops polling
+                // even when none of the keys change
+                'poll: while !watch.keys.is_empty() && !thread_stop.load(Ordering::SeqCst) {
+                    watch.wait();
+                    if thread_stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    watch.poll();
+                    while let Some(item) = watch.pending.pop_front() {
+                        if sender.send(item).is_err() {
+                            break 'poll;
+                        }
+                        if let Some(waker) = thread_waker.lock().unwrap().take() {
+                            waker.wake();
+                        }
+                    }
+                }
+                drop(sender);
+                if let Some(waker) = thread_waker.lock().unwrap().take() {
+                    waker.wake();
+                }
+            });
+
+            WatchStream {
+                receiver,
+                waker,
+                stop,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+pub use self::stream::WatchStream;
+
+#[cfg(all(test, feature = "stream"))]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::{DataType, MemoryBackend, SMC};
+
+    use four_char_code::four_char_code;
+
+    #[test]
+    fn dropped_stream_stops_polling() {
+        let backend = MemoryBackend::new();
+        backend
+            .insert(
+                four_char_code!("TC0P"),
+                DataType {
+                    id: four_char_code!("ui8 "),
+                    size: 1,
+                },
+                40_u8,
+            )
+            .unwrap();
+        let smc = SMC::with_backend(backend);
+
+        let stream = smc.watch_stream(vec![four_char_code!("TC0P")], Duration::from_millis(5));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(Arc::strong_count(&smc.0), 2);
+
+        drop(stream);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(Arc::strong_count(&smc.0), 1);
+    }
+}